@@ -0,0 +1,103 @@
+//! Measures how fast the CPU runs a small program, in emulated MHz.
+//!
+//! Run with `cargo bench --bench mhz`
+
+use std::time::{Duration, Instant};
+
+use radical_shyboy::bus::{Bus, OpenBusDevice};
+use radical_shyboy::ic6502::{CachedBus, IC6502};
+
+const CYCLES: u64 = 100_000_000;
+
+/// Copies a page of RAM while adding to every byte, with a subroutine call per byte
+const PROGRAM: [u8; 28] = [
+    0xA2, 0x00, // $8000 LDX #$00
+    0xBD, 0x00, 0x02, // $8002 LDA $0200,X
+    0x18, // $8005 CLC
+    0x69, 0x03, // $8006 ADC #$03
+    0x9D, 0x00, 0x03, // $8008 STA $0300,X
+    0xE6, 0x10, // $800B INC $10
+    0x20, 0x16, 0x80, // $800D JSR $8016
+    0xE8, // $8010 INX
+    0xD0, 0xEF, // $8011 BNE $8002
+    0x4C, 0x00, 0x80, // $8013 JMP $8000
+    0xB1, 0x20, // $8016 LDA ($20),Y
+    0x49, 0xFF, // $8018 EOR #$FF
+    0x2A, // $801A ROL A
+    0x60, // $801B RTS
+];
+
+/// Laid out like the bus of the NES: mirrored RAM, a few registers and banked ROM
+struct Memory {
+    ram: [u8; 0x0800],
+    registers: [u8; 0x20],
+    rom: Vec<u8>,
+    /// Offset into the ROM of each 8 KiB bank, like a mapper would switch them
+    banks: [usize; 4],
+}
+
+impl Bus for Memory {
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.ram[addr as usize & 0x07FF],
+            0x2000..=0x3FFF => self.registers[addr as usize & 0x0007],
+            0x4000..=0x401F => self.registers[addr as usize & 0x001F],
+            0x4020..=0x7FFF => 0,
+            0x8000..=0xFFFF => {
+                let bank = self.banks[(addr as usize - 0x8000) >> 13];
+                self.rom[bank + (addr as usize & 0x1FFF)]
+            }
+        }
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram[addr as usize & 0x07FF] = byte,
+            0x2000..=0x3FFF => self.registers[addr as usize & 0x0007] = byte,
+            0x4000..=0x401F => self.registers[addr as usize & 0x001F] = byte,
+            _ => {}
+        }
+    }
+}
+
+fn memory() -> Memory {
+    let mut rom = vec![0; 0x8000];
+    rom[..PROGRAM.len()].copy_from_slice(&PROGRAM);
+    let mut memory = Memory {
+        ram: [0; 0x0800],
+        registers: [0; 0x20],
+        rom,
+        banks: [0x0000, 0x2000, 0x4000, 0x6000],
+    };
+    memory.ram[0x20..0x22].copy_from_slice(&[0x00, 0x04]);
+    memory
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let mhz = CYCLES as f64 / elapsed.as_secs_f64() / 1_000_000.;
+    println!("{name:12} {mhz:8.2} MHz ({:.2}s)", elapsed.as_secs_f64());
+}
+
+fn main() {
+    let mut cpu: IC6502 = IC6502::builder().program_counter(0x8000).build();
+    let mut bus = memory();
+    let start = Instant::now();
+    let mut cycles = 0;
+    while cycles < CYCLES {
+        cycles += cpu
+            .cycle(&mut bus)
+            .expect("the program only touches mapped memory") as u64;
+    }
+    report("uncached", start.elapsed());
+
+    let mut cpu: IC6502 = IC6502::builder().program_counter(0x8000).build();
+    let mut bus = CachedBus::new(memory());
+    let start = Instant::now();
+    let mut cycles = 0;
+    while cycles < CYCLES {
+        cycles += cpu
+            .cycle_cached(&mut bus)
+            .expect("the program only touches mapped memory") as u64;
+    }
+    report("cached", start.elapsed());
+}
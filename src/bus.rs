@@ -1,41 +1,109 @@
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::ic6502::{IRQ_VECTOR, NMI_VECTOR, RESET_VECTOR};
+
 /// Trait that descibes a bus where not every connection is necissarily mapped to a device
 pub trait OpenBus {
-    fn read(&self, addr: u16) -> Option<u8>;
-    fn write(&mut self, addr: u16, byte: u8) -> Option<()>;
+    fn read(&self, addr: u16) -> Result<u8, BusError>;
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError>;
+
+    /// The lowest address that reaches the same memory as `addr`.
+    ///
+    /// Buses that mirror memory report it, so a [`CachedBus`](crate::ic6502::CachedBus)
+    /// knows that a write to one mirror changes the instructions in all of them
+    fn canonical_address(&self, addr: u16) -> u16 {
+        addr
+    }
+}
+
+/// An access on an [`OpenBus`] that no device answered to
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BusError {
+    pub addr: u16,
+    pub kind: AccessKind,
+}
+
+impl BusError {
+    pub fn read(addr: u16) -> Self {
+        Self {
+            addr,
+            kind: AccessKind::Read,
+        }
+    }
+
+    pub fn write(addr: u16) -> Self {
+        Self {
+            addr,
+            kind: AccessKind::Write,
+        }
+    }
+}
+
+impl Display for BusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            AccessKind::Read => write!(f, "nothing is mapped to read from ${:04X}", self.addr),
+            AccessKind::Write => write!(f, "nothing is mapped to write to ${:04X}", self.addr),
+        }
+    }
 }
 
+impl Error for BusError {}
+
 pub trait Bus {
     fn read(&self, addr: u16) -> u8;
     fn write(&mut self, addr: u16, byte: u8);
+
+    /// Number of addresses the device answers to starting at 0, `None` if it takes any address
+    fn size(&self) -> Option<usize> {
+        None
+    }
 }
 
 pub trait BusDevice<FuckyGenericContstrain> {
     type Bus: Bus;
-    fn cycle(&mut self, bus: &mut Self::Bus) -> Option<u8>;
+    type Error;
+    fn cycle(&mut self, bus: &mut Self::Bus) -> Result<u8, Self::Error>;
 }
 
 pub trait OpenBusDevice<B: OpenBus> {
-    fn cycle(&mut self, bus: &mut B) -> Option<u8>;
+    type Error;
+    fn cycle(&mut self, bus: &mut B) -> Result<u8, Self::Error>;
 }
 
 impl<B: Bus> OpenBus for B {
-    fn read(&self, addr: u16) -> Option<u8> {
-        Some(self.read(addr))
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
+        Ok(self.read(addr))
     }
 
-    fn write(&mut self, addr: u16, byte: u8) -> Option<()> {
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
         self.write(addr, byte);
-        Some(())
+        Ok(())
     }
 }
 
 impl<D: BusDevice<()>> OpenBusDevice<D::Bus> for D {
-    fn cycle(&mut self, bus: &mut D::Bus) -> Option<u8> {
+    type Error = D::Error;
+
+    fn cycle(&mut self, bus: &mut D::Bus) -> Result<u8, Self::Error> {
         self.cycle(bus)
     }
 }
 
-impl Bus for [u8; u16::MAX as usize] {
+/// Size of the address space of the 6502
+pub const ADDRESS_SPACE: usize = 0x10000;
+
+/// Plain RAM of any size, accesses past the end panic.
+///
+/// Put smaller RAM onto a [`MemoryMap`](crate::memory_map::MemoryMap) with a mirror mask
+/// to repeat it over a larger range
+impl<const N: usize> Bus for [u8; N] {
     fn read(&self, addr: u16) -> u8 {
         self[addr as usize]
     }
@@ -43,4 +111,170 @@ impl Bus for [u8; u16::MAX as usize] {
     fn write(&mut self, addr: u16, byte: u8) {
         self[addr as usize] = byte
     }
+
+    fn size(&self) -> Option<usize> {
+        Some(N)
+    }
+}
+
+/// RAM across the whole address space, the simplest bus to run a program on.
+///
+/// The vectors at the top of memory are plain RAM as well,
+/// so a program gets loaded together with the vectors pointing into it
+#[derive(Clone, Eq, PartialEq)]
+pub struct FlatMemory {
+    bytes: Box<[u8; ADDRESS_SPACE]>,
+}
+
+impl FlatMemory {
+    /// Memory filled with zeros
+    pub fn new() -> Self {
+        Self {
+            bytes: Box::new([0; ADDRESS_SPACE]),
+        }
+    }
+
+    /// Memory with a binary loaded at `offset` and the reset vector pointing at it
+    pub fn with_binary(offset: u16, binary: &[u8]) -> Self {
+        let mut memory = Self::new();
+        memory.load(offset, binary);
+        memory.set_reset_vector(offset);
+        memory
+    }
+
+    /// Copies a binary into memory, starting at `offset`
+    pub fn load(&mut self, offset: u16, binary: &[u8]) {
+        let start = offset as usize;
+        assert!(
+            start + binary.len() <= ADDRESS_SPACE,
+            "the binary doesn't fit into the address space"
+        );
+        self.bytes[start..start + binary.len()].copy_from_slice(binary);
+    }
+
+    pub fn set_reset_vector(&mut self, addr: u16) {
+        self.set_vector(RESET_VECTOR, addr);
+    }
+
+    pub fn set_nmi_vector(&mut self, addr: u16) {
+        self.set_vector(NMI_VECTOR, addr);
+    }
+
+    pub fn set_irq_vector(&mut self, addr: u16) {
+        self.set_vector(IRQ_VECTOR, addr);
+    }
+
+    pub fn with_reset_vector(mut self, addr: u16) -> Self {
+        self.set_reset_vector(addr);
+        self
+    }
+
+    pub fn with_nmi_vector(mut self, addr: u16) -> Self {
+        self.set_nmi_vector(addr);
+        self
+    }
+
+    pub fn with_irq_vector(mut self, addr: u16) -> Self {
+        self.set_irq_vector(addr);
+        self
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.bytes.as_mut_slice()
+    }
+
+    fn set_vector(&mut self, vector: u16, addr: u16) {
+        let [low_byte, high_byte] = addr.to_le_bytes();
+        self.bytes[vector as usize] = low_byte;
+        self.bytes[vector as usize + 1] = high_byte;
+    }
+}
+
+impl Default for FlatMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for FlatMemory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlatMemory").finish_non_exhaustive()
+    }
+}
+
+impl Bus for FlatMemory {
+    fn read(&self, addr: u16) -> u8 {
+        self.bytes[addr as usize]
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) {
+        self.bytes[addr as usize] = byte
+    }
+}
+
+/// Direction of a single bus access
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A single access on the bus
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BusAccess {
+    pub addr: u16,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+/// Wraps a bus and records every access made through it
+///
+/// Reads are logged through a `RefCell` since [`OpenBus::read`] only borrows the bus
+pub struct TracingBus<'a, B: OpenBus> {
+    bus: &'a mut B,
+    log: RefCell<Vec<BusAccess>>,
+}
+
+impl<'a, B: OpenBus> TracingBus<'a, B> {
+    pub fn new(bus: &'a mut B) -> Self {
+        Self {
+            bus,
+            log: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Consumes the wrapper and returns all accesses in the order they happened
+    pub fn into_log(self) -> Vec<BusAccess> {
+        self.log.into_inner()
+    }
+}
+
+impl<B: OpenBus> OpenBus for TracingBus<'_, B> {
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
+        let value = self.bus.read(addr)?;
+        self.log.borrow_mut().push(BusAccess {
+            addr,
+            value,
+            kind: AccessKind::Read,
+        });
+        Ok(value)
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
+        self.bus.write(addr, byte)?;
+        self.log.get_mut().push(BusAccess {
+            addr,
+            value: byte,
+            kind: AccessKind::Write,
+        });
+        Ok(())
+    }
+    fn canonical_address(&self, addr: u16) -> u16 {
+        self.bus.canonical_address(addr)
+    }
 }
@@ -0,0 +1,365 @@
+use std::{marker::PhantomData, ops::RangeInclusive};
+
+use crate::{
+    bus::{ADDRESS_SPACE, BusError, OpenBus},
+    ic6502::{CpuError, Fault, Hook, IC6502, Ricoh2A03, Variant},
+};
+
+/// Runs the instruction of a single opcode
+type Handler<V, B, H> = fn(&mut IC6502<V>, &mut Prefetched<'_, B, V>, &mut H) -> Result<u8, Fault>;
+
+/// Copy of [`IC6502::execute`] for a single opcode,
+/// so the compiler can throw away everything the instruction doesn't do
+fn execute_opcode<V: Variant, B: OpenBus, H: Hook<V>, const OPCODE: u8>(
+    cpu: &mut IC6502<V>,
+    bus: &mut Prefetched<'_, B, V>,
+    hooks: &mut H,
+) -> Result<u8, Fault> {
+    let instruction = const { V::OPCODES[OPCODE as usize].instruction() };
+    cpu.execute(bus, OPCODE, instruction, hooks)
+}
+
+/// The handlers of all opcodes, indexed by the high and the low nibble of the opcode
+macro_rules! handlers {
+    ($($high:literal)*) => {
+        [$(handlers!(@row $high: 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15)),*]
+    };
+    (@row $high:literal: $($low:literal)*) => {
+        [$(execute_opcode::<V, B, H, { $high * 16 + $low }> as Handler<V, B, H>),*]
+    };
+}
+
+/// The bytes an instruction was decoded from
+#[derive(Copy, Clone)]
+struct Decoded {
+    /// The opcode followed by the operands, only the first `len` bytes belong to the instruction
+    bytes: [u8; 3],
+    len: u8,
+}
+
+/// Wraps a bus and remembers the instruction the CPU decoded at every address,
+/// so [`IC6502::cycle_cached`] doesn't have to fetch and decode it again.
+///
+/// Writes through the wrapper drop the instructions they overlap,
+/// in every mirror the bus reports through [`OpenBus::canonical_address`].
+/// Anything that changes memory behind its back, like a mapper switching banks,
+/// has to [`CachedBus::invalidate`] the addresses it affects
+pub struct CachedBus<B: OpenBus, V: Variant = Ricoh2A03> {
+    bus: B,
+    instructions: Box<[Option<Decoded>]>,
+    variant: PhantomData<V>,
+}
+
+impl<B: OpenBus, V: Variant> CachedBus<B, V> {
+    pub fn new(bus: B) -> Self {
+        Self {
+            bus,
+            instructions: vec![None; ADDRESS_SPACE].into_boxed_slice(),
+            variant: PhantomData,
+        }
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.bus
+    }
+
+    /// Writes through the returned bus don't invalidate anything
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+
+    pub fn into_inner(self) -> B {
+        self.bus
+    }
+
+    /// Drops the instructions that overlap the addresses
+    pub fn invalidate(&mut self, range: RangeInclusive<u16>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let (start, end) = range.into_inner();
+        if end - start >= V::ADDRESS_MASK {
+            return self.clear();
+        }
+
+        for addr in start..=end {
+            self.drop_overlapping(addr);
+        }
+    }
+
+    /// Drops every instruction, e.g. after loading another program
+    pub fn clear(&mut self) {
+        self.instructions.fill(None);
+    }
+
+    /// Drops the instructions that overlap the address, wherever it is mirrored
+    fn drop_overlapping(&mut self, addr: u16) {
+        let addr = self.bus.canonical_address(addr & V::ADDRESS_MASK);
+        // instructions that start up to two bytes earlier reach the address
+        for offset in 0..3 {
+            self.instructions[(addr.wrapping_sub(offset) & V::ADDRESS_MASK) as usize] = None;
+        }
+    }
+
+    /// Fetches and decodes the instruction at the program counter the first time it runs
+    fn decode(&mut self, program_counter: u16) -> Result<Decoded, CpuError> {
+        // mirrors share their instructions
+        let addr = program_counter & V::ADDRESS_MASK;
+        let slot = self.bus.canonical_address(addr);
+        if let Some(decoded) = self.instructions[slot as usize] {
+            return Ok(decoded);
+        }
+
+        let error = |opcode, fault| CpuError {
+            program_counter,
+            opcode,
+            fault,
+        };
+
+        let opcode = self
+            .bus
+            .read(addr)
+            .map_err(|bus_error| error(None, bus_error.into()))?;
+        let len = V::decode(opcode).bytes;
+
+        let mut bytes = [opcode, 0, 0];
+        // writes only find instructions whose bytes follow each other in every mirror
+        let mut contiguous = true;
+        for offset in 1..len {
+            let operand = addr.wrapping_add(offset as u16) & V::ADDRESS_MASK;
+            bytes[offset as usize] = self
+                .bus
+                .read(operand)
+                .map_err(|bus_error| error(Some(opcode), bus_error.into()))?;
+            contiguous &= self.bus.canonical_address(operand) == slot.wrapping_add(offset as u16);
+        }
+
+        let decoded = Decoded { bytes, len };
+        if contiguous {
+            self.instructions[slot as usize] = Some(decoded);
+        }
+        Ok(decoded)
+    }
+}
+
+impl<B: OpenBus, V: Variant> OpenBus for CachedBus<B, V> {
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
+        self.bus.read(addr)
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
+        self.bus.write(addr, byte)?;
+        self.drop_overlapping(addr);
+        Ok(())
+    }
+
+    fn canonical_address(&self, addr: u16) -> u16 {
+        self.bus.canonical_address(addr)
+    }
+}
+
+/// Answers reads of the instruction that is running out of the cache
+struct Prefetched<'a, B: OpenBus, V: Variant> {
+    bus: &'a mut CachedBus<B, V>,
+    program_counter: u16,
+    decoded: Decoded,
+}
+
+impl<B: OpenBus, V: Variant> OpenBus for Prefetched<'_, B, V> {
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
+        let offset = addr.wrapping_sub(self.program_counter);
+        match offset < self.decoded.len as u16 {
+            true => Ok(self.decoded.bytes[offset as usize]),
+            false => self.bus.read(addr & V::ADDRESS_MASK),
+        }
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
+        self.bus.write(addr & V::ADDRESS_MASK, byte)
+    }
+    fn canonical_address(&self, addr: u16) -> u16 {
+        self.bus.canonical_address(addr & V::ADDRESS_MASK)
+    }
+}
+
+impl<V: Variant> IC6502<V> {
+    /// Executes a single instruction like [`OpenBusDevice::cycle`](crate::bus::OpenBusDevice::cycle),
+    /// but takes it out of the cache of the bus instead of fetching and decoding it again.
+    ///
+    /// The bytes of an instruction are fetched in one go the first time it runs,
+    /// after that only its data accesses and dummy reads reach the bus.
+    /// Use [`OpenBusDevice::cycle`](crate::bus::OpenBusDevice::cycle) or [`IC6502::tick`] where the exact bus activity matters
+    pub fn cycle_cached<B: OpenBus>(&mut self, bus: &mut CachedBus<B, V>) -> Result<u8, CpuError> {
+        self.cycle_cached_with_hooks(bus, &mut ())
+    }
+
+    /// Executes a single instruction out of the cache like [`IC6502::cycle_cached`]
+    /// and shows it to the hooks like [`IC6502::cycle_with_hooks`]
+    pub fn cycle_cached_with_hooks<B: OpenBus, H: Hook<V>>(
+        &mut self,
+        bus: &mut CachedBus<B, V>,
+        hooks: &mut H,
+    ) -> Result<u8, CpuError> {
+        // interrupt sequences and the halted states aren't instructions that could be cached
+        if self.jammed || self.waiting || !self.ready() || self.interrupt_due() {
+            return self.cycle_with_hooks(bus, hooks);
+        }
+
+        let program_counter = self.program_counter;
+        let decoded = bus.decode(program_counter)?;
+
+        let mut prefetched = Prefetched {
+            bus,
+            program_counter,
+            decoded,
+        };
+        let opcode = decoded.bytes[0] as usize;
+        let handlers = const { &handlers!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15) };
+        handlers[opcode >> 4][opcode & 0x0F](self, &mut prefetched, hooks).map_err(|fault| {
+            CpuError {
+                program_counter,
+                opcode: Some(decoded.bytes[0]),
+                fault,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bus::FlatMemory,
+        ic6502::Mos6507,
+        memory_map::{MemoryMap, Unmapped},
+    };
+
+    /// Runs the instruction at `addr` through the cache, then replaces its last byte with `patch`
+    fn cache_and_patch<V: Variant>(
+        addr: u16,
+        program: &[u8],
+        patch: u8,
+    ) -> (IC6502<V>, CachedBus<FlatMemory, V>) {
+        let address_of =
+            |offset: usize| (addr.wrapping_add(offset as u16) & V::ADDRESS_MASK) as usize;
+
+        let mut memory = FlatMemory::new();
+        for (offset, byte) in program.iter().enumerate() {
+            memory.as_mut_slice()[address_of(offset)] = *byte;
+        }
+        let mut bus = CachedBus::new(memory);
+        let mut cpu = IC6502::<V>::builder().program_counter(addr).build();
+        cpu.cycle_cached(&mut bus).unwrap();
+
+        bus.inner_mut().as_mut_slice()[address_of(program.len() - 1)] = patch;
+        cpu.set_program_counter(addr);
+        (cpu, bus)
+    }
+
+    #[test]
+    fn stale_without_invalidate() {
+        // INX patched to INY
+        let (mut cpu, mut bus) = cache_and_patch::<Ricoh2A03>(0x8000, &[0xE8], 0xC8);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!((cpu.register_x(), cpu.register_y()), (2, 0));
+    }
+
+    #[test]
+    fn invalidate_full_range() {
+        let (mut cpu, mut bus) = cache_and_patch::<Ricoh2A03>(0x8000, &[0xE8], 0xC8);
+        bus.invalidate(0x0000..=0xFFFF);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!((cpu.register_x(), cpu.register_y()), (1, 1));
+    }
+
+    #[test]
+    fn invalidate_reaches_back_across_zero() {
+        // LDA #$01 at $FFFF with its operand at $0000, patched to LDA #$02
+        let (mut cpu, mut bus) = cache_and_patch::<Ricoh2A03>(0xFFFF, &[0xA9, 0x01], 0x02);
+        bus.invalidate(0x0000..=0x0000);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!(cpu.accumulator(), 0x02);
+
+        // LDA $0000 at $FFFF with the high byte at $0001, patched to LDA $0100
+        let (mut cpu, mut bus) = cache_and_patch::<Ricoh2A03>(0xFFFF, &[0xAD, 0x00, 0x00], 0x01);
+        bus.inner_mut().as_mut_slice()[0x0100] = 0x42;
+        bus.invalidate(0x0001..=0x0001);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!(cpu.accumulator(), 0x42);
+    }
+
+    #[test]
+    fn invalidate_from_zero() {
+        let (mut cpu, mut bus) = cache_and_patch::<Ricoh2A03>(0x0000, &[0xE8], 0xC8);
+        bus.invalidate(0x0000..=0x0010);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!((cpu.register_x(), cpu.register_y()), (1, 1));
+    }
+
+    #[test]
+    fn write_through_the_cache_invalidates() {
+        let (mut cpu, mut bus) = cache_and_patch::<Ricoh2A03>(0x0200, &[0xA9, 0x01], 0x01);
+        bus.write(0x0201, 0x02).unwrap();
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!(cpu.accumulator(), 0x02);
+    }
+
+    #[test]
+    fn invalidate_through_address_mirrors() {
+        // the 6507 runs $F000 out of the slot of $1000
+        let (mut cpu, mut bus) = cache_and_patch::<Mos6507>(0xF000, &[0xE8], 0xC8);
+        bus.invalidate(0xF000..=0xF000);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!((cpu.register_x(), cpu.register_y()), (1, 1));
+
+        // a range as wide as the address bus drops everything
+        let (mut cpu, mut bus) = cache_and_patch::<Mos6507>(0xF000, &[0xE8], 0xC8);
+        bus.invalidate(0x4000..=0x5FFF);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!((cpu.register_x(), cpu.register_y()), (1, 1));
+    }
+
+    /// 2 KiB of RAM mirrored four times, with a program at its start
+    fn mirrored_ram(program: &[u8]) -> CachedBus<MemoryMap> {
+        let mut ram = [0; 0x800];
+        ram[..program.len()].copy_from_slice(program);
+        let mut map = MemoryMap::new(Unmapped::Fail);
+        map.map_mirrored(0x0000..=0x1FFF, 0x07FF, ram);
+        CachedBus::new(map)
+    }
+
+    #[test]
+    fn write_through_a_mirror_invalidates() {
+        let mut bus = mirrored_ram(&[0xE8]);
+        let mut cpu: IC6502 = IC6502::builder().program_counter(0x0000).build();
+        cpu.cycle_cached(&mut bus).unwrap();
+
+        // INX patched to INY through the second mirror
+        bus.write(0x0800, 0xC8).unwrap();
+        cpu.set_program_counter(0x0000);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!((cpu.register_x(), cpu.register_y()), (1, 1));
+
+        // and back to INX through the last one, running out of the third
+        bus.write(0x1800, 0xE8).unwrap();
+        cpu.set_program_counter(0x1000);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!((cpu.register_x(), cpu.register_y()), (2, 1));
+    }
+
+    #[test]
+    fn instructions_across_a_mirror_boundary() {
+        // LDA #$01 with its operand in the first byte of the next mirror
+        let mut bus = mirrored_ram(&[0x01]);
+        bus.write(0x07FF, 0xA9).unwrap();
+        let mut cpu: IC6502 = IC6502::builder().program_counter(0x07FF).build();
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!(cpu.accumulator(), 0x01);
+
+        bus.write(0x0000, 0x02).unwrap();
+        cpu.set_program_counter(0x07FF);
+        cpu.cycle_cached(&mut bus).unwrap();
+        assert_eq!(cpu.accumulator(), 0x02);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::ic6502::{IC6502, Interrupt, OperationKind, Variant};
+
+/// The stack page holds at most this many return addresses,
+/// older frames are overwritten on the real stack anyway
+const MAX_DEPTH: usize = 128;
+
+/// How a frame on the [`CallStack`] was entered
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FrameKind {
+    Subroutine,
+    Request,
+    NonMaskable,
+    Break,
+}
+
+impl FrameKind {
+    /// Whether RTI rather than RTS leaves the frame
+    pub fn is_interrupt(&self) -> bool {
+        !matches!(self, FrameKind::Subroutine)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    /// Address of the JSR or BRK, for hardware interrupts the instruction they interrupted
+    pub caller: u16,
+    /// Address of the subroutine or interrupt handler
+    pub target: u16,
+}
+
+impl Frame {
+    /// Where the matching RTS or RTI continues
+    pub fn return_address(&self) -> u16 {
+        match self.kind {
+            FrameKind::Subroutine => self.caller.wrapping_add(3),
+            FrameKind::Break => self.caller.wrapping_add(2),
+            FrameKind::Request | FrameKind::NonMaskable => self.caller,
+        }
+    }
+}
+
+/// A RTS or RTI that didn't leave the frame on top of the [`CallStack`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReturnMismatch {
+    /// Address of the RTS or RTI
+    pub program_counter: u16,
+    /// Address it continued at
+    pub return_address: u16,
+    /// Frame that was on top of the call stack, `None` if it was empty
+    pub expected: Option<Frame>,
+}
+
+/// Shadow of the return addresses on the stack, kept by the CPU when it is enabled through
+/// [`IC6502::enable_call_stack`].
+///
+/// A return that doesn't match the innermost frame gets recorded as a [`ReturnMismatch`].
+/// If it matches a frame further out, the frames in between are dropped,
+/// otherwise the call stack stays as it is. That way pushing an address and returning to it,
+/// a common way to jump through a table, doesn't throw the call stack off
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+    mismatches: Vec<ReturnMismatch>,
+}
+
+impl CallStack {
+    /// Frames from the outermost to the innermost one
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Frames from the innermost to the outermost one, like a debugger shows them
+    pub fn backtrace(&self) -> impl Iterator<Item = &Frame> {
+        self.frames.iter().rev()
+    }
+
+    pub fn mismatches(&self) -> &[ReturnMismatch] {
+        &self.mismatches
+    }
+
+    /// Hands out the mismatches recorded so far and forgets about them
+    pub fn take_mismatches(&mut self) -> Vec<ReturnMismatch> {
+        std::mem::take(&mut self.mismatches)
+    }
+
+    fn call(&mut self, frame: Frame) {
+        if self.frames.len() == MAX_DEPTH {
+            self.frames.remove(0);
+        }
+        self.frames.push(frame);
+    }
+
+    fn ret(&mut self, program_counter: u16, return_address: u16, interrupt: bool) {
+        let matching = self.frames.iter().rposition(|frame| {
+            frame.kind.is_interrupt() == interrupt && frame.return_address() == return_address
+        });
+
+        let innermost = matching.is_some_and(|index| index + 1 == self.frames.len());
+        if !innermost {
+            self.mismatches.push(ReturnMismatch {
+                program_counter,
+                return_address,
+                expected: self.frames.last().copied(),
+            });
+        }
+
+        if let Some(index) = matching {
+            self.frames.truncate(index);
+        }
+    }
+}
+
+impl<V: Variant> IC6502<V> {
+    /// Starts keeping a [`CallStack`], it starts out empty
+    pub fn enable_call_stack(&mut self) {
+        self.call_stack.get_or_insert_default();
+    }
+
+    /// Stops keeping the call stack and hands out what it holds
+    pub fn disable_call_stack(&mut self) -> Option<CallStack> {
+        self.call_stack.take().map(|call_stack| *call_stack)
+    }
+
+    pub fn call_stack(&self) -> Option<&CallStack> {
+        self.call_stack.as_deref()
+    }
+
+    pub fn call_stack_mut(&mut self) -> Option<&mut CallStack> {
+        self.call_stack.as_deref_mut()
+    }
+
+    /// Records an instruction that jumps to `target`,
+    /// has to run before the program counter moves on
+    pub(super) fn track_instruction(&mut self, kind: OperationKind, target: u16) {
+        let program_counter = self.program_counter;
+        let Some(call_stack) = self.call_stack.as_deref_mut() else {
+            return;
+        };
+
+        let kind = match kind {
+            OperationKind::JumpToSubRoutine => FrameKind::Subroutine,
+            OperationKind::Break => FrameKind::Break,
+            OperationKind::ReturnFromSubroutine => {
+                call_stack.ret(program_counter, target, false);
+                return;
+            }
+            OperationKind::ReturnFromInterrupt => {
+                call_stack.ret(program_counter, target, true);
+                return;
+            }
+            _ => return,
+        };
+
+        call_stack.call(Frame {
+            kind,
+            caller: program_counter,
+            target,
+        });
+    }
+
+    /// Records an interrupt sequence that jumped to its handler at `target`,
+    /// has to run before the program counter moves on
+    pub(super) fn track_interrupt(&mut self, interrupt: Interrupt, target: u16) {
+        let program_counter = self.program_counter;
+        let Some(call_stack) = self.call_stack.as_deref_mut() else {
+            return;
+        };
+
+        let kind = match interrupt {
+            Interrupt::Reset => {
+                call_stack.frames.clear();
+                return;
+            }
+            Interrupt::NonMaskable => FrameKind::NonMaskable,
+            Interrupt::Request => FrameKind::Request,
+        };
+
+        call_stack.call(Frame {
+            kind,
+            caller: program_counter,
+            target,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{FlatMemory, OpenBusDevice};
+
+    /// JSR $0210, which does JSR $0220 and both return
+    const NESTED: [(u16, &[u8]); 3] = [
+        (0x0200, &[0x20, 0x10, 0x02, 0xEA]),
+        (0x0210, &[0x20, 0x20, 0x02, 0x60]),
+        (0x0220, &[0x60]),
+    ];
+
+    fn setup(program: &[(u16, &[u8])]) -> (IC6502, FlatMemory) {
+        let mut memory = FlatMemory::new();
+        for (offset, bytes) in program {
+            memory.load(*offset, bytes);
+        }
+        let mut cpu = IC6502::builder().program_counter(0x0200).build();
+        cpu.enable_call_stack();
+        (cpu, memory)
+    }
+
+    fn run(cpu: &mut IC6502, memory: &mut FlatMemory, instructions: usize) {
+        for _ in 0..instructions {
+            cpu.cycle(memory).unwrap();
+        }
+    }
+
+    fn frames(cpu: &IC6502) -> Vec<Frame> {
+        cpu.call_stack().unwrap().frames().to_vec()
+    }
+
+    const OUTER: Frame = Frame {
+        kind: FrameKind::Subroutine,
+        caller: 0x0200,
+        target: 0x0210,
+    };
+
+    const INNER: Frame = Frame {
+        kind: FrameKind::Subroutine,
+        caller: 0x0210,
+        target: 0x0220,
+    };
+
+    #[test]
+    fn only_kept_while_enabled() {
+        let (mut cpu, mut memory) = setup(&NESTED);
+        cpu.disable_call_stack();
+        run(&mut cpu, &mut memory, 1);
+        assert!(cpu.call_stack().is_none());
+
+        cpu.enable_call_stack();
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(frames(&cpu), [INNER]);
+        assert_eq!(cpu.disable_call_stack().unwrap().frames(), [INNER]);
+    }
+
+    #[test]
+    fn ignored_when_comparing_cpus() {
+        let (mut cpu, mut memory) = setup(&NESTED);
+        run(&mut cpu, &mut memory, 1);
+        let mut other = cpu.clone();
+        other.disable_call_stack();
+        assert_eq!(cpu, other);
+    }
+
+    #[test]
+    fn subroutines_nest_and_return() {
+        let (mut cpu, mut memory) = setup(&NESTED);
+        run(&mut cpu, &mut memory, 2);
+        assert_eq!(frames(&cpu), [OUTER, INNER]);
+        let backtrace: Vec<_> = cpu.call_stack().unwrap().backtrace().copied().collect();
+        assert_eq!(backtrace, [INNER, OUTER]);
+
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(frames(&cpu), [OUTER]);
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(cpu.registers().program_counter, 0x0203);
+        assert_eq!(frames(&cpu), []);
+        assert!(cpu.call_stack().unwrap().mismatches().is_empty());
+    }
+
+    #[test]
+    fn ticking_keeps_the_same_frames() {
+        let (mut cpu, mut memory) = setup(&NESTED);
+        let mut seen = Vec::new();
+        while seen.len() < 4 {
+            if cpu.tick(&mut memory).unwrap() {
+                seen.push(frames(&cpu));
+            }
+        }
+        assert_eq!(seen, [vec![OUTER], vec![OUTER, INNER], vec![OUTER], vec![]]);
+    }
+
+    #[test]
+    fn interrupts_are_left_through_rti() {
+        let (mut cpu, mut memory) =
+            setup(&[(0x0200, &[0x58, 0x00, 0xEA, 0xEA]), (0x0300, &[0x40])]);
+        memory.set_irq_vector(0x0300);
+        memory.set_nmi_vector(0x0300);
+        run(&mut cpu, &mut memory, 2);
+        let brk = Frame {
+            kind: FrameKind::Break,
+            caller: 0x0201,
+            target: 0x0300,
+        };
+        assert_eq!(frames(&cpu), [brk]);
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(cpu.registers().program_counter, brk.return_address());
+        assert_eq!(frames(&cpu), []);
+
+        cpu.set_nmi(true);
+        while cpu.registers().program_counter != 0x0300 {
+            run(&mut cpu, &mut memory, 1);
+        }
+        let [nmi] = frames(&cpu)[..] else {
+            panic!("expected the NMI frame");
+        };
+        assert_eq!(nmi.kind, FrameKind::NonMaskable);
+        assert_eq!(nmi.target, 0x0300);
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(cpu.registers().program_counter, nmi.caller);
+        assert_eq!(frames(&cpu), []);
+        assert!(cpu.call_stack().unwrap().mismatches().is_empty());
+    }
+
+    #[test]
+    fn unknown_returns_are_mismatches() {
+        // pushes $022F and returns to $0230 like a jump table does
+        let (mut cpu, mut memory) = setup(&[
+            (0x0200, &[0x20, 0x10, 0x02]),
+            (0x0210, &[0xA9, 0x02, 0x48, 0xA9, 0x2F, 0x48, 0x60]),
+        ]);
+        run(&mut cpu, &mut memory, 6);
+        assert_eq!(cpu.registers().program_counter, 0x0230);
+        assert_eq!(frames(&cpu), [OUTER]);
+
+        let call_stack = cpu.call_stack_mut().unwrap();
+        let mismatch = ReturnMismatch {
+            program_counter: 0x0216,
+            return_address: 0x0230,
+            expected: Some(OUTER),
+        };
+        assert_eq!(call_stack.take_mismatches(), [mismatch]);
+        assert!(call_stack.mismatches().is_empty());
+    }
+
+    #[test]
+    fn returning_past_frames_drops_them() {
+        // the inner subroutine drops its own return address and returns for its caller
+        let (mut cpu, mut memory) = setup(&[
+            (0x0200, &[0x20, 0x10, 0x02, 0xEA]),
+            (0x0210, &[0x20, 0x20, 0x02]),
+            (0x0220, &[0x68, 0x68, 0x60]),
+        ]);
+        run(&mut cpu, &mut memory, 5);
+        assert_eq!(cpu.registers().program_counter, 0x0203);
+        assert_eq!(frames(&cpu), []);
+        let mismatches = cpu.call_stack().unwrap().mismatches();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].expected, Some(INNER));
+    }
+
+    #[test]
+    fn depth_is_limited_and_reset_clears() {
+        // JSR to itself forever
+        let (mut cpu, mut memory) = setup(&[(0x0200, &[0x20, 0x00, 0x02])]);
+        run(&mut cpu, &mut memory, MAX_DEPTH + 10);
+        assert_eq!(frames(&cpu).len(), MAX_DEPTH);
+
+        memory.set_reset_vector(0x0200);
+        cpu.reset();
+        run(&mut cpu, &mut memory, 1);
+        assert_eq!(frames(&cpu), []);
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{bus::BusError, ic6502::OperationArgument};
+
+/// Reason the CPU couldn't carry on with an instruction
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Fault {
+    /// Nothing on the bus answered to an access, it holds the faulting address
+    Bus(BusError),
+    /// An operation got a register value where it needs an address or the other way round
+    InvalidArgument(OperationArgument),
+    /// A JAM or STP opcode locked up the CPU, only a reset gets it going again
+    Jammed,
+    /// The cycle isn't part of the sequence of the instruction in flight.
+    ///
+    /// The instruction table and the cycle stepped sequencer disagree,
+    /// which is a bug in the emulator rather than in the program
+    UnexpectedCycle(u8),
+}
+
+impl From<BusError> for Fault {
+    fn from(error: BusError) -> Self {
+        Self::Bus(error)
+    }
+}
+
+/// Error of a [`IC6502`](crate::ic6502::IC6502) together with the instruction it happened on
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CpuError {
+    /// Address of the instruction that faulted
+    pub program_counter: u16,
+    /// `None` if the opcode wasn't fetched yet or an interrupt sequence was running
+    pub opcode: Option<u8>,
+    pub fault: Fault,
+}
+
+impl Display for CpuError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.opcode {
+            Some(opcode) => write!(
+                f,
+                "instruction ${opcode:02X} at ${:04X} failed: ",
+                self.program_counter
+            )?,
+            None => write!(f, "cycle at ${:04X} failed: ", self.program_counter)?,
+        }
+
+        match self.fault {
+            Fault::Bus(error) => write!(f, "{error}"),
+            Fault::InvalidArgument(argument) => write!(f, "invalid argument {argument:?}"),
+            Fault::Jammed => write!(f, "the CPU is jammed"),
+            Fault::UnexpectedCycle(cycle) => write!(f, "unexpected cycle {cycle}"),
+        }
+    }
+}
+
+impl Error for CpuError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.fault {
+            Fault::Bus(error) => Some(error),
+            _ => None,
+        }
+    }
+}
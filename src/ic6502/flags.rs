@@ -1,5 +1,8 @@
 use serde_derive::{Deserialize, Serialize};
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
+};
 
 #[derive(Debug, Copy, Clone, Deserialize, Serialize, Eq, PartialEq)]
 pub enum Flags {
@@ -13,37 +16,150 @@ pub enum Flags {
     Negative = (1 << 7),
 }
 
-macro_rules! set_flag {
-    ($field:expr, $flag:ident) => {
-        set_flag!($field, $flag, true)
-    };
-    ($field:expr, $flag:ident, $cond:expr) => {
-        match $cond {
-            true => $field |= Flags::$flag,
-            false => $field &= !Flags::$flag,
+impl Flags {
+    /// Whether the bit of the flag is set in a byte, e.g. bit 7 of a result for `Negative`
+    pub const fn is_set(self, byte: u8) -> bool {
+        byte & self as u8 != 0
+    }
+}
+
+/// The P register of the CPU.
+///
+/// `Break` and `Unused` aren't real flags, they only show up in the byte that gets pushed.
+/// Going through [`StatusRegister::to_pushed`] and [`StatusRegister::from_bits`]
+/// makes sure they are handled like on the real chip,
+/// serialized states are taken as they are
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct StatusRegister(u8);
+
+impl StatusRegister {
+    /// Status with the flags of the bits, `Break` gets dropped and `Unused` is always set
+    pub const fn from_bits(bits: u8) -> Self {
+        Self((bits & !(Flags::Break as u8)) | Flags::Unused as u8)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn contains(self, flag: Flags) -> bool {
+        self.0 & flag as u8 == flag as u8
+    }
+
+    pub fn set(&mut self, flag: Flags, value: bool) {
+        match value {
+            true => self.insert(flag),
+            false => self.remove(flag),
         }
-    };
+    }
+
+    pub fn insert(&mut self, flag: Flags) {
+        self.0 |= flag;
+    }
+
+    pub fn remove(&mut self, flag: Flags) {
+        self.0 &= !flag;
+    }
+
+    /// Byte that gets pushed onto the stack, `Unused` is always set.
+    ///
+    /// `Break` is set when PHP or BRK push the status and clear for hardware interrupts
+    pub fn to_pushed(self, brk: bool) -> u8 {
+        let bits = self.0 | Flags::Unused;
+        match brk {
+            true => bits | Flags::Break,
+            false => bits & !Flags::Break,
+        }
+    }
+
+    /// Status that PLP and RTI pull off the stack, `Break` gets dropped
+    pub fn from_pulled(byte: u8) -> Self {
+        Self::from_bits(byte)
+    }
+}
+
+/// All flags clear
+impl Default for StatusRegister {
+    fn default() -> Self {
+        Self::from_bits(0)
+    }
+}
+
+impl From<u8> for StatusRegister {
+    fn from(bits: u8) -> Self {
+        Self::from_bits(bits)
+    }
+}
+
+impl From<StatusRegister> for u8 {
+    fn from(status: StatusRegister) -> Self {
+        status.bits()
+    }
 }
-pub(super) use set_flag;
-macro_rules! unset_flag {
-    ($field:expr, $flag:ident) => {
-        set_flag!($field, $flag, false)
-    };
+
+/// Any combination of [`Flags`], e.g. the ones an instruction reads or writes.
+///
+/// Unlike the [`StatusRegister`] it takes the bits as they are and can be empty
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct FlagSet(u8);
+
+impl FlagSet {
+    pub const EMPTY: Self = Self(0);
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn contains(self, flag: Flags) -> bool {
+        self.0 & flag as u8 == flag as u8
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
 }
-pub(super) use unset_flag;
 
-macro_rules! is_set {
-    ($field:expr, $flag:ident) => {
-        Flags::$flag & $field == Flags::$flag as u8
-    };
+impl From<Flags> for FlagSet {
+    fn from(flag: Flags) -> Self {
+        Self(flag as u8)
+    }
 }
-pub(super) use is_set;
 
-// impl Flags {
-//     pub fn is_set(self, val: u8) -> bool {
-//         self & val == self as u8
-//     }
-// }
+impl BitOr<Flags> for FlagSet {
+    type Output = FlagSet;
+
+    fn bitor(self, rhs: Flags) -> Self::Output {
+        Self(self.0 | rhs as u8)
+    }
+}
+
+/// Shows the flags as `NV-BDIZC`, upper case when set and lower case when clear
+impl Display for StatusRegister {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use Flags::*;
+        for (flag, name) in [
+            (Negative, 'N'),
+            (Overflow, 'V'),
+            (Unused, '-'),
+            (Break, 'B'),
+            (DecimalMode, 'D'),
+            (InterruptDisable, 'I'),
+            (Zero, 'Z'),
+            (Carry, 'C'),
+        ] {
+            match (flag, self.contains(flag)) {
+                (Unused, _) | (_, true) => write!(f, "{name}")?,
+                (_, false) => write!(f, "{}", name.to_ascii_lowercase())?,
+            }
+        }
+        Ok(())
+    }
+}
 
 impl BitAnd<u8> for Flags {
     type Output = u8;
@@ -146,3 +262,41 @@ impl PartialEq<Flags> for u8 {
         *self == *other as u8
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn break_and_unused_only_on_the_stack() {
+        for status in [
+            StatusRegister::from_bits(0xFF),
+            StatusRegister::from(0xFF),
+            StatusRegister::from_pulled(0xFF),
+        ] {
+            assert_eq!(status.bits(), 0xEF);
+            assert_eq!(status.to_pushed(true), 0xFF);
+            assert_eq!(status.to_pushed(false), 0xEF);
+        }
+        assert_eq!(StatusRegister::default().bits(), 0x20);
+        assert_eq!(StatusRegister::from_bits(0x10).to_string(), "nv-bdizc");
+    }
+
+    #[test]
+    fn flags_in_bytes() {
+        assert!(Flags::Negative.is_set(0x80));
+        assert!(!Flags::Negative.is_set(0x7F));
+        assert!(Flags::Overflow.is_set(0x40));
+        assert!(Flags::Carry.is_set(0x01));
+    }
+
+    #[test]
+    fn flag_sets_keep_their_bits() {
+        assert!(FlagSet::default().is_empty());
+        assert!(!FlagSet::EMPTY.contains(Flags::Unused));
+        let set = FlagSet::from(Flags::Break) | Flags::Carry;
+        assert_eq!(set.bits(), 0x11);
+        assert!(set.contains(Flags::Break));
+        assert!(!set.contains(Flags::Zero));
+    }
+}
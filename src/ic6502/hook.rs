@@ -0,0 +1,222 @@
+use crate::ic6502::{IC6502, Instruction, Ricoh2A03, Variant};
+
+/// What a [`Hook`] gets to see of the instruction that runs
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Execution {
+    /// Address of the opcode
+    pub program_counter: u16,
+    pub opcode: u8,
+    pub instruction: Instruction,
+    /// Address the instruction reads, writes or jumps to.
+    ///
+    /// `None` if it only works on registers, the stack or the branch offset,
+    /// and for JSR which reads its target only while it runs
+    pub effective_address: Option<u16>,
+}
+
+/// Gets to observe every instruction run through [`IC6502::cycle_with_hooks`],
+/// [`IC6502::tick_with_hooks`], [`IC6502::cycle_cached_with_hooks`]
+/// or [`Scheduler::run_until_with_hooks`](crate::scheduler::Scheduler::run_until_with_hooks).
+/// The plain entry points run without hooks, interrupt sequences aren't shown to them.
+///
+/// Both methods do nothing by default, so a hook only implements what it needs.
+/// Hooks are stacked by putting them in a tuple, `()` is the hook that does nothing
+/// and costs nothing since the calls get compiled away
+pub trait Hook<V: Variant = Ricoh2A03> {
+    /// Called once the operand is resolved, before the instruction changes any register or memory
+    fn before(&mut self, _cpu: &IC6502<V>, _execution: &Execution) {}
+
+    /// Called once the instruction finished, with the cycles it took
+    fn after(&mut self, _cpu: &IC6502<V>, _execution: &Execution, _cycles: u8) {}
+}
+
+impl<V: Variant> Hook<V> for () {}
+
+/// Runs the first hook before the second one, and after it the other way round
+impl<V: Variant, A: Hook<V>, B: Hook<V>> Hook<V> for (A, B) {
+    fn before(&mut self, cpu: &IC6502<V>, execution: &Execution) {
+        self.0.before(cpu, execution);
+        self.1.before(cpu, execution);
+    }
+
+    fn after(&mut self, cpu: &IC6502<V>, execution: &Execution, cycles: u8) {
+        self.1.after(cpu, execution, cycles);
+        self.0.after(cpu, execution, cycles);
+    }
+}
+
+/// A hook that can be switched off at runtime
+impl<V: Variant, H: Hook<V>> Hook<V> for Option<H> {
+    fn before(&mut self, cpu: &IC6502<V>, execution: &Execution) {
+        if let Some(hook) = self {
+            hook.before(cpu, execution);
+        }
+    }
+
+    fn after(&mut self, cpu: &IC6502<V>, execution: &Execution, cycles: u8) {
+        if let Some(hook) = self {
+            hook.after(cpu, execution, cycles);
+        }
+    }
+}
+
+impl<V: Variant, H: Hook<V> + ?Sized> Hook<V> for &mut H {
+    fn before(&mut self, cpu: &IC6502<V>, execution: &Execution) {
+        (**self).before(cpu, execution);
+    }
+
+    fn after(&mut self, cpu: &IC6502<V>, execution: &Execution, cycles: u8) {
+        (**self).after(cpu, execution, cycles);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bus::FlatMemory,
+        ic6502::{CachedBus, Registers},
+        scheduler::Scheduler,
+    };
+
+    /// Loads, stores, a page crossing read, a subroutine with a loop, an indirect jump
+    /// and a read-modify-write instruction running forever
+    const PROGRAM: [(u16, &[u8]); 4] = [
+        (
+            0x0200,
+            &[
+                0xA9, 0x05, 0x85, 0x10, 0xA6, 0x10, 0xE8, 0xBD, 0xFD, 0x02, 0x20, 0x20, 0x02, 0x6C,
+                0x30, 0x02,
+            ],
+        ),
+        (0x0220, &[0xCA, 0xD0, 0xFD, 0x60]),
+        (0x0230, &[0x40, 0x02]),
+        (0x0240, &[0xEE, 0x10, 0x00, 0x4C, 0x40, 0x02]),
+    ];
+
+    const INSTRUCTIONS: usize = 30;
+
+    /// Remembers what the hooks saw, with the registers at the time
+    #[derive(Debug, Default, PartialEq)]
+    struct Recorder {
+        before: Vec<(Execution, Registers)>,
+        after: Vec<(Execution, Registers, u8)>,
+    }
+
+    impl Hook for Recorder {
+        fn before(&mut self, cpu: &IC6502, execution: &Execution) {
+            self.before.push((*execution, cpu.registers()));
+        }
+
+        fn after(&mut self, cpu: &IC6502, execution: &Execution, cycles: u8) {
+            self.after.push((*execution, cpu.registers(), cycles));
+        }
+    }
+
+    fn setup() -> (IC6502, FlatMemory) {
+        let mut memory = FlatMemory::new();
+        for (offset, bytes) in PROGRAM {
+            memory.load(offset, bytes);
+        }
+        let cpu = IC6502::builder().program_counter(0x0200).build();
+        (cpu, memory)
+    }
+
+    fn cycle_mode() -> Recorder {
+        let (mut cpu, mut memory) = setup();
+        let mut recorder = Recorder::default();
+        for _ in 0..INSTRUCTIONS {
+            cpu.cycle_with_hooks(&mut memory, &mut recorder).unwrap();
+        }
+        recorder
+    }
+
+    #[test]
+    fn every_instruction_is_shown() {
+        let recorder = cycle_mode();
+        assert_eq!(recorder.before.len(), INSTRUCTIONS);
+        assert_eq!(recorder.after.len(), INSTRUCTIONS);
+
+        // LDA $02FD,X with X = 6 reads across the page
+        let (execution, registers) = recorder.before[4];
+        assert_eq!(execution.effective_address, Some(0x0303));
+        assert_eq!(registers.accumulator, 0x05);
+        assert_eq!(recorder.after[4].2, 5);
+
+        // JMP ($0230)
+        let jump = recorder
+            .after
+            .iter()
+            .find(|(execution, ..)| execution.opcode == 0x6C);
+        assert_eq!(jump.unwrap().0.effective_address, Some(0x0240));
+    }
+
+    #[test]
+    fn tick_shows_the_same() {
+        let (mut cpu, mut memory) = setup();
+        let mut recorder = Recorder::default();
+        while recorder.after.len() < INSTRUCTIONS {
+            cpu.tick_with_hooks(&mut memory, &mut recorder).unwrap();
+        }
+        assert_eq!(recorder, cycle_mode());
+    }
+
+    #[test]
+    fn cache_shows_the_same() {
+        let (mut cpu, memory) = setup();
+        let mut bus = CachedBus::new(memory);
+        let mut recorder = Recorder::default();
+        for _ in 0..INSTRUCTIONS {
+            cpu.cycle_cached_with_hooks(&mut bus, &mut recorder)
+                .unwrap();
+        }
+        assert_eq!(recorder, cycle_mode());
+    }
+
+    #[test]
+    fn scheduler_shows_the_same() {
+        let expected = cycle_mode();
+        let cycles: u64 = expected
+            .after
+            .iter()
+            .map(|(.., cycles)| *cycles as u64)
+            .sum();
+
+        let (cpu, memory) = setup();
+        let mut scheduler: Scheduler<_> = Scheduler::new(cpu, memory, 1);
+        let mut recorder = Recorder::default();
+        scheduler
+            .run_until_with_hooks(cycles, &mut recorder)
+            .unwrap();
+        assert_eq!(recorder, expected);
+    }
+
+    #[test]
+    fn interrupt_sequences_are_not_shown() {
+        let run = |tick: bool| {
+            let (mut cpu, mut memory) = setup();
+            memory.set_nmi_vector(0x0240);
+            cpu.set_nmi(true);
+            let mut recorder = Recorder::default();
+            while recorder.after.len() < INSTRUCTIONS {
+                match tick {
+                    true => cpu.tick_with_hooks(&mut memory, &mut recorder).map(|_| ()),
+                    false => cpu.cycle_with_hooks(&mut memory, &mut recorder).map(|_| ()),
+                }
+                .unwrap();
+            }
+            (recorder, memory)
+        };
+
+        let (recorder, memory) = run(true);
+        assert_eq!(recorder, run(false).0);
+        // the first instruction polls the NMI, the handler runs right after it
+        assert_eq!(recorder.before[1].0.program_counter, 0x0240);
+        for (execution, ..) in recorder.after {
+            assert_eq!(
+                execution.opcode,
+                memory.as_slice()[execution.program_counter as usize]
+            );
+        }
+    }
+}
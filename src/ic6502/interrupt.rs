@@ -0,0 +1,282 @@
+use crate::ic6502::{Flags, IC6502, Variant};
+
+pub const NMI_VECTOR: u16 = 0xFFFA;
+pub const RESET_VECTOR: u16 = 0xFFFC;
+pub const IRQ_VECTOR: u16 = 0xFFFE;
+
+/// Hardware interrupts of the 6502, in order of priority
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Interrupt {
+    Reset,
+    NonMaskable,
+    Request,
+}
+
+impl Interrupt {
+    /// Address the CPU reads the interrupt handler location from
+    pub fn vector(&self) -> u16 {
+        match self {
+            Interrupt::Reset => RESET_VECTOR,
+            Interrupt::NonMaskable => NMI_VECTOR,
+            Interrupt::Request => IRQ_VECTOR,
+        }
+    }
+}
+
+/// State of the interrupt inputs of the CPU
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub(super) struct InterruptLines {
+    irq: bool,
+    nmi: bool,
+    /// NMI is edge triggered, so a rising edge stays pending until it got serviced
+    nmi_pending: bool,
+    reset_pending: bool,
+    /// Whether the instruction that ran last polled an interrupt,
+    /// its sequence then runs instead of the next instruction
+    due: bool,
+}
+
+impl<V: Variant> IC6502<V> {
+    /// Sets the level of the IRQ line.
+    ///
+    /// The CPU keeps servicing the interrupt for as long as the line is asserted
+    /// and the `InterruptDisable` flag is clear.
+    /// Like on the real chip the lines are polled on the second to last cycle of an instruction,
+    /// an interrupt that comes in later waits for one more instruction.
+    /// Chips without the pin ignore it
+    pub fn set_irq(&mut self, asserted: bool) {
+        self.lines.irq = asserted && V::INTERRUPT_LINES;
+    }
+
+    /// Sets the level of the NMI line, asserting it triggers a single interrupt.
+    ///
+    /// It is polled like the IRQ line and ignored the same way, see [`IC6502::set_irq`]
+    pub fn set_nmi(&mut self, asserted: bool) {
+        if !V::INTERRUPT_LINES {
+            return;
+        }
+
+        if asserted && !self.lines.nmi {
+            self.lines.nmi_pending = true;
+        }
+        self.lines.nmi = asserted;
+    }
+
+    /// Pulls the RESET line.
+    ///
+    /// The instruction in flight gets dropped and the reset sequence runs on the next cycle.
+    /// Like on the real chip the registers are kept, the stack pointer moves down by three
+    /// and interrupts get disabled. This is the only way to get a jammed CPU going again
+    pub fn reset(&mut self) {
+        self.lines.reset_pending = true;
+    }
+
+    /// Whether the CPU has to service a reset that was requested
+    pub(super) fn reset_pending(&self) -> bool {
+        self.lines.reset_pending
+    }
+
+    /// Whether any interrupt line is active, wakes the CPU up after WAI
+    /// even if the `InterruptDisable` flag keeps the IRQ from being serviced
+    pub(super) fn interrupt_asserted(&self) -> bool {
+        self.lines.reset_pending || self.lines.nmi_pending || self.lines.irq
+    }
+
+    /// Whether the interrupt lines ask for an interrupt the CPU would service right now
+    #[inline(always)]
+    pub(super) fn poll_interrupt(&self) -> bool {
+        self.lines.nmi_pending || (self.lines.irq && !self.status.contains(Flags::InterruptDisable))
+    }
+
+    /// Decides whether the interrupt sequence runs instead of the next instruction
+    #[inline(always)]
+    pub(super) fn set_interrupt_due(&mut self, due: bool) {
+        self.lines.due = due;
+    }
+
+    /// Whether the next cycle starts an interrupt sequence instead of an instruction
+    #[inline(always)]
+    pub(super) fn interrupt_due(&self) -> bool {
+        self.lines.reset_pending || self.lines.due
+    }
+
+    /// Starts servicing the interrupt that is due, in order of priority.
+    ///
+    /// A reset is acknowledged right away, an NMI only once it took over the vector
+    pub(super) fn take_interrupt(&mut self) -> Option<Interrupt> {
+        if self.lines.reset_pending {
+            self.lines.reset_pending = false;
+            self.lines.due = false;
+            self.jammed = false;
+            return Some(Interrupt::Reset);
+        }
+
+        if !std::mem::take(&mut self.lines.due) {
+            return None;
+        }
+
+        match self.lines.nmi_pending {
+            true => Some(Interrupt::NonMaskable),
+            // the sequence runs even if the line was released in the meantime
+            false => Some(Interrupt::Request),
+        }
+    }
+
+    /// Acknowledges a pending NMI while BRK or an IRQ push the return address.
+    ///
+    /// The NMI hijacks their sequence, it continues with the NMI vector
+    /// and still pushes the status the way it started out with
+    pub(super) fn take_nmi(&mut self) -> bool {
+        std::mem::take(&mut self.lines.nmi_pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bus::{FlatMemory, OpenBusDevice},
+        ic6502::StatusRegister,
+    };
+
+    /// Handlers are NOPs, the IRQ one at `$0400` and the NMI one at `$0500`
+    fn setup(program: &[u8], status: u8) -> (IC6502, FlatMemory) {
+        let mut memory = FlatMemory::with_binary(0x0200, program)
+            .with_irq_vector(0x0400)
+            .with_nmi_vector(0x0500);
+        memory.load(0x0400, &[0xEA; 4]);
+        memory.load(0x0500, &[0xEA; 4]);
+        let cpu = IC6502::builder()
+            .program_counter(0x0200)
+            .stack_pointer(0xFC)
+            .status(StatusRegister::from_bits(status))
+            .build();
+        (cpu, memory)
+    }
+
+    /// Finishes the instruction or interrupt sequence in flight
+    fn step(cpu: &mut IC6502, memory: &mut FlatMemory, tick: bool) {
+        match tick {
+            true => while !cpu.tick(memory).unwrap() {},
+            false => _ = cpu.cycle(memory).unwrap(),
+        }
+    }
+
+    /// Where every instruction and interrupt sequence left the program counter
+    fn trace(cpu: &mut IC6502, memory: &mut FlatMemory, tick: bool, steps: usize) -> Vec<u16> {
+        (0..steps)
+            .map(|_| {
+                step(cpu, memory, tick);
+                cpu.program_counter()
+            })
+            .collect()
+    }
+
+    const I: u8 = Flags::InterruptDisable as u8;
+
+    #[test]
+    fn changing_interrupt_disable_takes_an_instruction() {
+        // CLI, SEI and PLP with the flags it pulls, RTI changes the flag right away
+        let cases: [(&[u8], u8, u8, &[u16]); 5] = [
+            (&[0x58, 0xEA, 0xEA], I, 0, &[0x0201, 0x0202, 0x0400]),
+            (&[0x78, 0xEA, 0xEA], 0, 0, &[0x0201, 0x0400, 0x0401]),
+            (&[0x28, 0xEA, 0xEA], I, 0, &[0x0201, 0x0202, 0x0400]),
+            (&[0x28, 0xEA, 0xEA], 0, I, &[0x0201, 0x0400, 0x0401]),
+            (&[0x40], I, 0, &[0x0300, 0x0400, 0x0401]),
+        ];
+
+        for (program, status, pulled, expected) in cases {
+            for tick in [false, true] {
+                let (mut cpu, mut memory) = setup(program, status);
+                // the status, then the return address for RTI
+                memory.load(0x01FD, &[pulled, 0x00, 0x03]);
+                memory.load(0x0300, &[0xEA]);
+                cpu.set_irq(true);
+                let trace = trace(&mut cpu, &mut memory, tick, expected.len());
+                assert_eq!(trace, expected, "{:02X} ticked: {tick}", program[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn taken_branches_only_poll_when_crossing_a_page() {
+        // BEQ to the next instruction and BEQ across the page to $0300,
+        // the IRQ comes in while the operand is read
+        for (offset, program, expected) in [
+            (0x0200, [0xF0, 0x00, 0xEA, 0xEA], [0x0202, 0x0203, 0x0400]),
+            (0x02FC, [0xF0, 0x02, 0xEA, 0xEA], [0x0300, 0x0400, 0x0401]),
+        ] {
+            let (mut cpu, mut memory) = setup(&[], Flags::Zero as u8);
+            memory.load(offset, &program);
+            memory.load(0x0300, &[0xEA]);
+            cpu.set_program_counter(offset);
+
+            cpu.tick(&mut memory).unwrap();
+            cpu.set_irq(true);
+            assert_eq!(trace(&mut cpu, &mut memory, true, 3), expected);
+        }
+
+        // a line that was asserted before the branch is seen either way
+        for tick in [false, true] {
+            let (mut cpu, mut memory) = setup(&[0xF0, 0x00, 0xEA], Flags::Zero as u8);
+            cpu.set_irq(true);
+            assert_eq!(trace(&mut cpu, &mut memory, tick, 2), [0x0202, 0x0400]);
+        }
+    }
+
+    #[test]
+    fn nmi_hijacks_brk() {
+        // the NMI takes over the vector until BRK pushed the return address,
+        // later it waits for the first instruction of the IRQ handler
+        for cycle in 1..=7 {
+            let (mut cpu, mut memory) = setup(&[0x00, 0x00], 0);
+            for _ in 1..cycle {
+                cpu.tick(&mut memory).unwrap();
+            }
+            cpu.set_nmi(true);
+            let trace = trace(&mut cpu, &mut memory, true, 3);
+            let expected = match cycle {
+                1..=4 => [0x0500, 0x0501, 0x0502],
+                _ => [0x0400, 0x0401, 0x0500],
+            };
+            assert_eq!(trace, expected, "NMI on cycle {cycle}");
+            // BRK still pushes the status with the break flag
+            assert_eq!(
+                memory.as_slice()[0x01FA] & Flags::Break as u8,
+                Flags::Break as u8
+            );
+        }
+
+        let (mut cpu, mut memory) = setup(&[0x00, 0x00], 0);
+        cpu.set_nmi(true);
+        assert_eq!(trace(&mut cpu, &mut memory, false, 1), [0x0500]);
+    }
+
+    #[test]
+    fn nmi_hijacks_irq() {
+        for cycle in 1..=7 {
+            let (mut cpu, mut memory) = setup(&[0xEA, 0xEA], 0);
+            cpu.set_irq(true);
+            step(&mut cpu, &mut memory, true);
+            cpu.set_irq(false);
+            for _ in 1..cycle {
+                cpu.tick(&mut memory).unwrap();
+            }
+            cpu.set_nmi(true);
+            let trace = trace(&mut cpu, &mut memory, true, 3);
+            let expected = match cycle {
+                1..=4 => [0x0500, 0x0501, 0x0502],
+                _ => [0x0400, 0x0401, 0x0500],
+            };
+            assert_eq!(trace, expected, "NMI on cycle {cycle}");
+            // the IRQ still pushes the status without the break flag
+            assert_eq!(memory.as_slice()[0x01FA] & Flags::Break as u8, 0);
+        }
+
+        let (mut cpu, mut memory) = setup(&[0xEA, 0xEA], 0);
+        cpu.set_irq(true);
+        step(&mut cpu, &mut memory, false);
+        cpu.set_nmi(true);
+        assert_eq!(trace(&mut cpu, &mut memory, false, 1), [0x0500]);
+    }
+}
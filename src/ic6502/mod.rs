@@ -1,17 +1,54 @@
+use std::marker::PhantomData;
+
 use serde_derive::{Deserialize, Serialize};
 
 use crate::bus::{OpenBus, OpenBusDevice};
 
+mod cache;
+pub use cache::CachedBus;
+
+mod call_stack;
+pub use call_stack::{CallStack, Frame, FrameKind, ReturnMismatch};
+
+mod error;
+pub use error::{CpuError, Fault};
+
 mod flags;
 pub use flags::*;
 
+mod hook;
+pub use hook::{Execution, Hook};
+
 mod opcodes;
 use opcodes::Thingimagic;
+pub use opcodes::{OperationArgument, OperationKind};
+
+mod interrupt;
+use interrupt::InterruptLines;
+pub use interrupt::{IRQ_VECTOR, Interrupt, NMI_VECTOR, RESET_VECTOR};
+
+mod pins;
+use pins::Pins;
+
+mod registers;
+pub use registers::{IC6502Builder, Registers};
+
+mod unstable;
+pub use unstable::UnstableOpcodes;
+
+mod variant;
+use variant::AddressLines;
+pub use variant::{Mos6507, Nmos6502, Ricoh2A03, Rockwell65C02, Synertek65C02, Variant, Wdc65C02};
+
+mod stepped;
 pub use opcodes::{AdressingMode, Instruction, Operation};
+use stepped::InFlight;
 
 /// Represents the State of the 6502 Mikroprocessor
-#[derive(Debug, Copy, Clone, Default, Deserialize, Serialize, Eq, PartialEq)]
-pub struct IC6502 {
+///
+/// The [`Variant`] decides which member of the 6502 family it behaves like
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IC6502<V: Variant = Ricoh2A03> {
     #[serde(rename = "a")]
     accumulator: u8,
     #[serde(rename = "x")]
@@ -23,39 +60,307 @@ pub struct IC6502 {
     #[serde(rename = "pc")]
     program_counter: u16,
     #[serde(rename = "p")]
-    status: u8,
+    status: StatusRegister,
+    /// Progress of the instruction when running cycle by cycle through [`IC6502::tick`]
+    #[serde(skip)]
+    in_flight: InFlight,
+    /// Set by the JAM opcodes, only a reset gets the CPU going again
+    #[serde(skip)]
+    jammed: bool,
+    /// Set by WAI, any interrupt line gets the CPU going again
+    #[serde(skip)]
+    waiting: bool,
+    #[serde(skip)]
+    lines: InterruptLines,
+    #[serde(skip)]
+    pins: Pins,
+    #[serde(skip)]
+    unstable: UnstableOpcodes,
+    /// Only kept while enabled through [`IC6502::enable_call_stack`],
+    /// it's why the CPU is `Clone` but not `Copy` and it doesn't count when comparing CPUs
+    #[serde(skip)]
+    call_stack: Option<Box<CallStack>>,
+    #[serde(skip)]
+    variant: PhantomData<V>,
 }
 
 pub const STACK_PAGE: u16 = 0x0100;
 
-#[allow(unused, dropping_copy_types)]
-impl<B: OpenBus> OpenBusDevice<B> for IC6502 {
-    fn cycle(&mut self, bus: &mut B) -> Option<u8> {
-        let instruction = bus.read(self.program_counter)?;
+/// CPUs are equal when their [`Registers`] are,
+/// what they are doing in between and how they are configured doesn't count
+impl<V: Variant> PartialEq for IC6502<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.registers() == other.registers()
+    }
+}
+
+impl<V: Variant> Eq for IC6502<V> {}
+
+impl<V: Variant> IC6502<V> {
+    /// Address of the JAM or STP opcode that locked up the CPU, if it is jammed
+    pub fn jammed(&self) -> Option<u16> {
+        self.jammed.then_some(self.program_counter)
+    }
+
+    /// Whether the CPU stopped on WAI and is waiting for an interrupt
+    pub fn waiting(&self) -> bool {
+        self.waiting
+    }
+}
+
+impl<B: OpenBus, V: Variant> OpenBusDevice<B> for IC6502<V> {
+    type Error = CpuError;
+
+    /// Executes a single instruction and returns the number of cycles it took
+    ///
+    /// The bus sees the same accesses as when ticking through the instruction,
+    /// dummy reads and writes included.
+    ///
+    /// An interrupt the last instruction polled gets serviced before the next one,
+    /// its sequence counts as an instruction of its own.
+    /// Lines changed in between calls are only polled by the instruction that runs next.
+    ///
+    /// Fails with [`Fault::Jammed`] without touching the bus while the CPU is jammed.
+    /// While waiting for an interrupt or stalled by RDY every call idles for a single cycle
+    fn cycle(&mut self, bus: &mut B) -> Result<u8, CpuError> {
+        self.cycle_with_hooks(bus, &mut ())
+    }
+}
+
+impl<V: Variant> IC6502<V> {
+    /// Executes a single instruction like [`OpenBusDevice::cycle`] and shows it to the hooks.
+    ///
+    /// Interrupt sequences aren't instructions, the hooks don't get to see them
+    pub fn cycle_with_hooks(
+        &mut self,
+        bus: &mut impl OpenBus,
+        hooks: &mut impl Hook<V>,
+    ) -> Result<u8, CpuError> {
+        let bus = &mut AddressLines::<_, V>::new(bus);
+        let program_counter = self.program_counter;
+        let error = |opcode, fault| CpuError {
+            program_counter,
+            opcode,
+            fault,
+        };
+
+        if self.jammed && !self.reset_pending() {
+            return Err(error(None, Fault::Jammed));
+        }
+
+        if self.waiting {
+            if !self.interrupt_asserted() {
+                return Ok(1);
+            }
+            self.waiting = false;
+            self.set_interrupt_due(self.poll_interrupt());
+        }
+
+        if self
+            .stall(bus)
+            .map_err(|bus_error| error(None, bus_error.into()))?
+        {
+            return Ok(1);
+        }
+
+        if self.interrupt_due() {
+            // the interrupt sequence is only implemented cycle by cycle
+            let mut cycles = 1;
+            while !self.tick(bus)? {
+                cycles += 1;
+            }
+            return Ok(cycles);
+        }
+
+        let opcode = bus
+            .read(program_counter)
+            .map_err(|bus_error| error(None, bus_error.into()))?;
+
+        self.execute(bus, opcode, V::decode(opcode), hooks)
+            .map_err(|fault| error(Some(opcode), fault))
+    }
 
-        let Instruction::Valid {
+    /// Runs the instruction whose opcode was just fetched
+    #[inline(always)]
+    fn execute(
+        &mut self,
+        bus: &mut impl OpenBus,
+        opcode: u8,
+        instruction: Instruction,
+        hooks: &mut impl Hook<V>,
+    ) -> Result<u8, Fault> {
+        let Instruction {
             operation,
             addressing_mode,
             bytes,
             cycles,
-        } = instruction.into()
-        else {
-            // ! invalid instruction
-            // ! currently defined as noop
-            self.program_counter = self.program_counter.wrapping_add(1);
-            return None;
+        } = instruction;
+
+        let mut execution = Execution {
+            program_counter: self.program_counter,
+            opcode,
+            instruction,
+            effective_address: None,
+        };
+
+        // the single cycle NOPs of the 65C02 are done with the fetch
+        if cycles == 1 {
+            hooks.before(self, &execution);
+            self.program_counter = self.program_counter.wrapping_add(bytes as u16);
+            self.set_interrupt_due(self.poll_interrupt());
+            hooks.after(self, &execution, 1);
+            return Ok(1);
+        }
+
+        // the lines can't change while the instruction runs, only the flags can
+        let polled = self.poll_interrupt();
+
+        let (offset, argument, page_crossed) = match operation.kind() {
+            // JSR pushes the return address in between reading the two bytes of the target
+            OperationKind::JumpToSubRoutine => (
+                3,
+                OperationArgument::Pointer(self.program_counter.wrapping_add(1)),
+                false,
+            ),
+            _ => addressing_mode.read(self, bus, operation.has_page_penalty::<V>())?,
         };
 
-        let (offset, argument) = addressing_mode.read(self, bus)?;
+        if let OperationArgument::Pointer(addr) = argument
+            && operation.kind() != OperationKind::JumpToSubRoutine
+        {
+            execution.effective_address = Some(addr);
+        }
+        hooks.before(self, &execution);
+
+        let mut cycles = cycles;
+        if page_crossed && operation.has_page_penalty::<V>() {
+            cycles += 1;
+        }
+
+        // cycles that are spent internally still access the bus
+        match operation.kind() {
+            OperationKind::Pull
+            | OperationKind::ReturnFromSubroutine
+            | OperationKind::ReturnFromInterrupt => {
+                bus.read(STACK_PAGE.wrapping_add(self.stack_pointer as u16))?;
+            }
+            OperationKind::Halt => {
+                bus.read(self.program_counter.wrapping_add(1))?;
+            }
+            _ => {}
+        }
+
+        let result = operation.run(self, bus, argument)?;
+        let next = self.program_counter.wrapping_add(offset as u16);
+
+        if operation.has_decimal_penalty(self) {
+            bus.read(next)?;
+            cycles += 1;
+        }
+
+        // the long NOP of the 65C02 keeps reading its address until it used up its cycles
+        if let (Operation::NoOp, AdressingMode::Absolute, OperationArgument::Pointer(ptr)) =
+            (operation, addressing_mode, argument)
+        {
+            for _ in 4..cycles {
+                bus.read(ptr)?;
+            }
+        }
 
-        match operation.run(self, bus, argument)? {
-            Thingimagic::Jump(ptr) => self.program_counter = ptr,
-            Thingimagic::Increment => {
-                self.program_counter = self.program_counter.wrapping_add(offset as u16)
+        match result {
+            Thingimagic::Jump(ptr) => {
+                self.track_instruction(operation.kind(), ptr);
+                self.program_counter = ptr;
             }
+            Thingimagic::Branch(ptr) => {
+                // taken branches take one more cycle and another one if they leave the page,
+                // the high byte of the target isn't fixed up yet on that cycle
+                bus.read(next)?;
+                cycles += 1;
+                if opcodes::page_crossed(next, ptr) {
+                    bus.read((next & 0xFF00) | (ptr & 0x00FF))?;
+                    cycles += 1;
+                }
+                self.program_counter = ptr;
+            }
+            Thingimagic::Increment => self.program_counter = next,
+        };
+
+        // BRK doesn't poll at all, so the handler gets to run its first instruction
+        let due = match operation.kind() {
+            OperationKind::Break => false,
+            _ if operation.delays_interrupts() => polled,
+            _ => self.poll_interrupt(),
         };
+        self.set_interrupt_due(due);
+
+        hooks.after(self, &execution, cycles);
+        Ok(cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{FlatMemory, TracingBus};
+
+    /// JAM on the NMOS chips and STP on the WDC 65C02, with NOPs at the reset vector
+    fn jam<V: Variant>(opcode: u8) -> (IC6502<V>, FlatMemory) {
+        let mut memory = FlatMemory::with_binary(0x0200, &[opcode]).with_reset_vector(0x0300);
+        memory.load(0x0300, &[0xEA]);
+        let cpu = IC6502::builder().program_counter(0x0200).build();
+        (cpu, memory)
+    }
+
+    fn jammed<V: Variant>(cpu: &mut IC6502<V>, memory: &mut FlatMemory, tick: bool) {
+        assert_eq!(cpu.jammed(), Some(0x0200));
+        for _ in 0..3 {
+            let mut bus = TracingBus::new(memory);
+            let fault = match tick {
+                true => cpu.tick(&mut bus).map(|_| ()),
+                false => cpu.cycle(&mut bus).map(|_| ()),
+            }
+            .unwrap_err()
+            .fault;
+            assert_eq!(fault, Fault::Jammed);
+            assert!(bus.into_log().is_empty());
+        }
+        assert_eq!(cpu.jammed(), Some(0x0200));
+    }
+
+    fn jams_until_reset<V: Variant>(opcode: u8) {
+        for tick in [false, true] {
+            let (mut cpu, mut memory) = jam::<V>(opcode);
+            let error = match tick {
+                true => loop {
+                    if let Err(error) = cpu.tick(&mut memory) {
+                        break error;
+                    }
+                },
+                false => cpu.cycle(&mut memory).unwrap_err(),
+            };
+            assert_eq!(
+                error,
+                CpuError {
+                    program_counter: 0x0200,
+                    opcode: Some(opcode),
+                    fault: Fault::Jammed,
+                }
+            );
+            jammed(&mut cpu, &mut memory, tick);
+
+            cpu.reset();
+            cpu.cycle(&mut memory).unwrap();
+            assert_eq!(cpu.jammed(), None);
+            assert_eq!(cpu.program_counter(), 0x0300);
+            assert_eq!(cpu.cycle(&mut memory), Ok(2));
+        }
+    }
 
-        drop(0);
-        Some(1) //eventually this should return the count of cycles this instruction would have taken
+    #[test]
+    fn jam_halts_until_reset() {
+        jams_until_reset::<Nmos6502>(0x02);
+        jams_until_reset::<Ricoh2A03>(0xF2);
+        jams_until_reset::<Wdc65C02>(0xDB);
     }
 }
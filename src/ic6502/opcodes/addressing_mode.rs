@@ -1,14 +1,14 @@
 use serde_derive::{Deserialize, Serialize};
 
 use crate::{
-    bus::OpenBus,
+    bus::{BusError, OpenBus},
     ic6502::{
-        IC6502,
+        IC6502, Variant,
         opcodes::operation::OperationArgument::{self, *},
     },
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AdressingMode {
     #[serde(rename = "IMP")]
     Implied,
@@ -39,12 +39,31 @@ pub enum AdressingMode {
     IndirectIndexed,
     #[serde(rename = "IND")]
     AbsoluteIndirect,
+
+    // 65C02 only
+    #[serde(rename = "ZPI")]
+    ZeroPageIndirect,
+    #[serde(rename = "IAX")]
+    AbsoluteIndexedIndirect,
+    /// Zero page address followed by a branch offset, used by BBR and BBS
+    #[serde(rename = "ZPR")]
+    ZeroPageRelative,
 }
 
 impl AdressingMode {
-    /// Returns a tuple of the program counter offset caused by the read process
-    /// and the operation argument that was read
-    pub fn read(&self, cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+    /// Returns a tuple of the program counter offset caused by the read process,
+    /// the operation argument that was read and whether indexing crossed a page boundary
+    ///
+    /// Does the same dummy accesses as the real chip.
+    /// Operations with a page penalty skip the dummy read that happens
+    /// while the high byte gets fixed up, as long as indexing didn't cross a page
+    #[inline(always)]
+    pub fn read<V: Variant>(
+        &self,
+        cpu: &IC6502<V>,
+        bus: &impl OpenBus,
+        skips_fixup: bool,
+    ) -> Result<(u8, OperationArgument, bool), BusError> {
         use AdressingMode::*;
         match self {
             Implied => address_mode_imp(cpu, bus),
@@ -57,122 +76,276 @@ impl AdressingMode {
             IndexedZeroPageY => address_mode_zpy(cpu, bus),
 
             Absolute => address_mode_abs(cpu, bus),
-            IndexedAbsoluteX => address_mode_abx(cpu, bus),
-            IndexedAbsoluteY => address_mode_aby(cpu, bus),
+            IndexedAbsoluteX => address_mode_abx(cpu, bus, skips_fixup),
+            IndexedAbsoluteY => address_mode_aby(cpu, bus, skips_fixup),
 
             IndexedIndirect => address_mode_inx(cpu, bus),
-            IndirectIndexed => address_mode_iny(cpu, bus),
+            IndirectIndexed => address_mode_iny(cpu, bus, skips_fixup),
             AbsoluteIndirect => address_mode_ind(cpu, bus),
+
+            ZeroPageIndirect => address_mode_zpi(cpu, bus),
+            AbsoluteIndexedIndirect => address_mode_iax(cpu, bus),
+            ZeroPageRelative => address_mode_zpr(cpu, bus),
         }
     }
 }
 
-/// Implied Adress mode will either not need any data at all or read from Accumulator
+/// Implied Adress mode will either not need any data at all or read from Accumulator,
+/// the byte after the opcode gets read and thrown away
 #[inline(always)]
-fn address_mode_imp(cpu: &IC6502, _: &impl OpenBus) -> Option<(u8, OperationArgument)> {
-    Some((1, Value(cpu.accumulator)))
+fn address_mode_imp(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    bus.read(cpu.program_counter.wrapping_add(1))?;
+    Ok((1, Value(cpu.accumulator), false))
 }
 
 #[inline(always)]
-fn address_mode_imm(cpu: &IC6502, _: &impl OpenBus) -> Option<(u8, OperationArgument)> {
-    Some((2, Pointer(cpu.program_counter.wrapping_add(1))))
+fn address_mode_imm(
+    cpu: &IC6502<impl Variant>,
+    _: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    Ok((2, Pointer(cpu.program_counter.wrapping_add(1)), false))
 }
 
 #[inline(always)]
-fn address_mode_acc(cpu: &IC6502, _: &impl OpenBus) -> Option<(u8, OperationArgument)> {
-    Some((1, Value(cpu.accumulator)))
+fn address_mode_acc(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    bus.read(cpu.program_counter.wrapping_add(1))?;
+    Ok((1, Value(cpu.accumulator), false))
 }
 
 #[inline(always)]
-fn address_mode_rel(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
-    Some((2, Value(bus.read(cpu.program_counter.wrapping_add(1))?)))
+fn address_mode_rel(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    Ok((
+        2,
+        Value(bus.read(cpu.program_counter.wrapping_add(1))?),
+        false,
+    ))
 }
 
 #[inline(always)]
-fn address_mode_zp0(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_zp0(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
     let addr = bus.read(cpu.program_counter.wrapping_add(1))? as u16;
-    Some((2, Pointer(addr)))
+    Ok((2, Pointer(addr), false))
+}
+
+/// The index gets added on a cycle of its own,
+/// meanwhile the NMOS chips read the unindexed address and the 65C02 the operand
+#[inline(always)]
+fn zero_page_dummy_read<V: Variant>(
+    cpu: &IC6502<V>,
+    bus: &impl OpenBus,
+    addr: u8,
+) -> Result<u8, BusError> {
+    match V::CMOS {
+        true => bus.read(cpu.program_counter.wrapping_add(1)),
+        false => bus.read(addr as u16),
+    }
 }
 
 #[inline(always)]
-fn address_mode_zpx(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_zpx(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
     let addr = bus.read(cpu.program_counter.wrapping_add(1))?;
+    zero_page_dummy_read(cpu, bus, addr)?;
     let addr = addr.wrapping_add(cpu.register_x);
-    Some((2, Pointer(addr as u16)))
+    Ok((2, Pointer(addr as u16), false))
 }
 
 #[inline(always)]
-fn address_mode_zpy(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_zpy(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
     let addr = bus.read(cpu.program_counter.wrapping_add(1))?;
+    zero_page_dummy_read(cpu, bus, addr)?;
     let addr = addr.wrapping_add(cpu.register_y);
-    Some((2, Pointer(addr as u16)))
+    Ok((2, Pointer(addr as u16), false))
 }
 
 #[inline(always)]
-fn address_mode_abs(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_abs(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
     let addr = u16::from_le_bytes([
         bus.read(cpu.program_counter.wrapping_add(1))?,
         bus.read(cpu.program_counter.wrapping_add(2))?,
     ]);
-    Some((3, Pointer(addr)))
+    Ok((3, Pointer(addr), false))
+}
+
+/// Adds the index to a base address, the carry into the high byte takes an extra cycle.
+///
+/// During that cycle the NMOS chips read the address without the carry
+/// and the 65C02 rereads the last operand byte at `last_operand`
+#[inline(always)]
+fn index_with_fixup<V: Variant>(
+    bus: &impl OpenBus,
+    addr: u16,
+    index: u8,
+    last_operand: u16,
+    skips_fixup: bool,
+) -> Result<(u16, bool), BusError> {
+    let indexed = addr.wrapping_add(index as u16);
+    let crossed = page_crossed(addr, indexed);
+    if crossed || !skips_fixup {
+        match V::CMOS {
+            true => bus.read(last_operand)?,
+            false => bus.read((addr & 0xFF00) | (indexed & 0x00FF))?,
+        };
+    }
+    Ok((indexed, crossed))
 }
 
 #[inline(always)]
-fn address_mode_abx(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_abx<V: Variant>(
+    cpu: &IC6502<V>,
+    bus: &impl OpenBus,
+    skips_fixup: bool,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    let last_operand = cpu.program_counter.wrapping_add(2);
     let addr = u16::from_le_bytes([
         bus.read(cpu.program_counter.wrapping_add(1))?,
-        bus.read(cpu.program_counter.wrapping_add(2))?,
+        bus.read(last_operand)?,
     ]);
-    let addr = addr.wrapping_add(cpu.register_x as u16);
-    Some((3, Pointer(addr)))
+    let (indexed, crossed) =
+        index_with_fixup::<V>(bus, addr, cpu.register_x, last_operand, skips_fixup)?;
+    Ok((3, Pointer(indexed), crossed))
 }
 
 #[inline(always)]
-fn address_mode_aby(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_aby<V: Variant>(
+    cpu: &IC6502<V>,
+    bus: &impl OpenBus,
+    skips_fixup: bool,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    let last_operand = cpu.program_counter.wrapping_add(2);
     let addr = u16::from_le_bytes([
         bus.read(cpu.program_counter.wrapping_add(1))?,
-        bus.read(cpu.program_counter.wrapping_add(2))?,
+        bus.read(last_operand)?,
     ]);
-    let addr = addr.wrapping_add(cpu.register_y as u16);
-    Some((3, Pointer(addr)))
+    let (indexed, crossed) =
+        index_with_fixup::<V>(bus, addr, cpu.register_y, last_operand, skips_fixup)?;
+    Ok((3, Pointer(indexed), crossed))
 }
 
 #[inline(always)]
-fn address_mode_ind(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_ind<V: Variant>(
+    cpu: &IC6502<V>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    let last_operand = cpu.program_counter.wrapping_add(2);
     let addr_low_byte = u16::from_le_bytes([
         bus.read(cpu.program_counter.wrapping_add(1))?,
-        bus.read(cpu.program_counter.wrapping_add(2))?,
+        bus.read(last_operand)?,
     ]);
 
-    // due to a hardware bug the addition doesnt carry into the high byte
-    let addr_high_byte = u16::from_le_bytes([
-        bus.read(cpu.program_counter.wrapping_add(1))?
-            .wrapping_add(1),
-        bus.read(cpu.program_counter.wrapping_add(2))?,
-    ]);
+    // due to a hardware bug the addition doesnt carry into the high byte,
+    // the 65C02 fixed it at the cost of a cycle
+    let addr_high_byte = match V::INDIRECT_JUMP_BUG {
+        false => {
+            bus.read(last_operand)?;
+            addr_low_byte.wrapping_add(1)
+        }
+        true => {
+            let [low_byte, high_byte] = addr_low_byte.to_le_bytes();
+            u16::from_le_bytes([low_byte.wrapping_add(1), high_byte])
+        }
+    };
 
     let addr = u16::from_le_bytes([bus.read(addr_low_byte)?, bus.read(addr_high_byte)?]);
 
-    Some((3, Pointer(addr)))
+    Ok((3, Pointer(addr), false))
 }
 
 #[inline(always)]
-fn address_mode_inx(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
+fn address_mode_inx(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
     let addr = bus.read(cpu.program_counter.wrapping_add(1))?;
+    zero_page_dummy_read(cpu, bus, addr)?;
     let addr = addr.wrapping_add(cpu.register_x);
 
     let low_byte = bus.read(addr as u16)?;
     let high_byte = bus.read(addr.wrapping_add(1) as u16)?;
 
     let addr = u16::from_le_bytes([low_byte, high_byte]);
-    Some((2, Pointer(addr)))
+    Ok((2, Pointer(addr), false))
 }
 
 #[inline(always)]
-fn address_mode_iny(cpu: &IC6502, bus: &impl OpenBus) -> Option<(u8, OperationArgument)> {
-    let addr = bus.read(cpu.program_counter.wrapping_add(1))? as u8;
+fn address_mode_iny<V: Variant>(
+    cpu: &IC6502<V>,
+    bus: &impl OpenBus,
+    skips_fixup: bool,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    let operand = cpu.program_counter.wrapping_add(1);
+    let addr = bus.read(operand)?;
     let low_byte = bus.read(addr as u16)?;
     let high_byte = bus.read(addr.wrapping_add(1) as u16)?;
-    let addr = u16::from_le_bytes([low_byte, high_byte]).wrapping_add(cpu.register_y as u16);
-    Some((2, Pointer(addr)))
+    let addr = u16::from_le_bytes([low_byte, high_byte]);
+    let (indexed, crossed) =
+        index_with_fixup::<V>(bus, addr, cpu.register_y, operand, skips_fixup)?;
+    Ok((2, Pointer(indexed), crossed))
+}
+
+#[inline(always)]
+fn address_mode_zpi(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    let addr = bus.read(cpu.program_counter.wrapping_add(1))?;
+    let low_byte = bus.read(addr as u16)?;
+    let high_byte = bus.read(addr.wrapping_add(1) as u16)?;
+    Ok((2, Pointer(u16::from_le_bytes([low_byte, high_byte])), false))
+}
+
+/// The 65C02 spends a cycle on adding the index, rereading the last operand byte
+#[inline(always)]
+fn address_mode_iax(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    let last_operand = cpu.program_counter.wrapping_add(2);
+    let addr = u16::from_le_bytes([
+        bus.read(cpu.program_counter.wrapping_add(1))?,
+        bus.read(last_operand)?,
+    ]);
+    bus.read(last_operand)?;
+    let addr = addr.wrapping_add(cpu.register_x as u16);
+    let addr = u16::from_le_bytes([bus.read(addr)?, bus.read(addr.wrapping_add(1))?]);
+    Ok((3, Pointer(addr), false))
+}
+
+/// Reads the zero page byte whose bit gets tested, twice like the real chip does.
+/// The operation reads the branch offset itself
+#[inline(always)]
+fn address_mode_zpr(
+    cpu: &IC6502<impl Variant>,
+    bus: &impl OpenBus,
+) -> Result<(u8, OperationArgument, bool), BusError> {
+    let addr = bus.read(cpu.program_counter.wrapping_add(1))? as u16;
+    let value = bus.read(addr)?;
+    bus.read(addr)?;
+    Ok((3, Value(value), false))
+}
+
+/// Whether two addresses lie on different pages
+#[inline(always)]
+pub(crate) fn page_crossed(a: u16, b: u16) -> bool {
+    a & 0xFF00 != b & 0xFF00
 }
@@ -0,0 +1,119 @@
+use crate::ic6502::opcodes::{AdressingMode, Instruction, Operation, nmos6502};
+
+/// Decodes the opcode like the WDC 65C02 does, it is the only one with WAI and STP
+pub(crate) const fn wdc65c02(opcode: u8) -> Instruction {
+    match opcode {
+        0xCB => short_form!(WaitForInterrupt, Implied, 1, 3),
+        0xDB => short_form!(Stop, Implied, 1, 3),
+        _ => rockwell65c02(opcode),
+    }
+}
+
+/// Decodes the opcode like the Rockwell 65C02 does, it adds the bit instructions
+pub(crate) const fn rockwell65c02(opcode: u8) -> Instruction {
+    match opcode {
+        0x07 => short_form!(ResetMemoryBit(0), ZeroPage, 2, 5),
+        0x17 => short_form!(ResetMemoryBit(1), ZeroPage, 2, 5),
+        0x27 => short_form!(ResetMemoryBit(2), ZeroPage, 2, 5),
+        0x37 => short_form!(ResetMemoryBit(3), ZeroPage, 2, 5),
+        0x47 => short_form!(ResetMemoryBit(4), ZeroPage, 2, 5),
+        0x57 => short_form!(ResetMemoryBit(5), ZeroPage, 2, 5),
+        0x67 => short_form!(ResetMemoryBit(6), ZeroPage, 2, 5),
+        0x77 => short_form!(ResetMemoryBit(7), ZeroPage, 2, 5),
+        0x87 => short_form!(SetMemoryBit(0), ZeroPage, 2, 5),
+        0x97 => short_form!(SetMemoryBit(1), ZeroPage, 2, 5),
+        0xA7 => short_form!(SetMemoryBit(2), ZeroPage, 2, 5),
+        0xB7 => short_form!(SetMemoryBit(3), ZeroPage, 2, 5),
+        0xC7 => short_form!(SetMemoryBit(4), ZeroPage, 2, 5),
+        0xD7 => short_form!(SetMemoryBit(5), ZeroPage, 2, 5),
+        0xE7 => short_form!(SetMemoryBit(6), ZeroPage, 2, 5),
+        0xF7 => short_form!(SetMemoryBit(7), ZeroPage, 2, 5),
+
+        0x0F => short_form!(BranchOnBitReset(0), ZeroPageRelative, 3, 5),
+        0x1F => short_form!(BranchOnBitReset(1), ZeroPageRelative, 3, 5),
+        0x2F => short_form!(BranchOnBitReset(2), ZeroPageRelative, 3, 5),
+        0x3F => short_form!(BranchOnBitReset(3), ZeroPageRelative, 3, 5),
+        0x4F => short_form!(BranchOnBitReset(4), ZeroPageRelative, 3, 5),
+        0x5F => short_form!(BranchOnBitReset(5), ZeroPageRelative, 3, 5),
+        0x6F => short_form!(BranchOnBitReset(6), ZeroPageRelative, 3, 5),
+        0x7F => short_form!(BranchOnBitReset(7), ZeroPageRelative, 3, 5),
+        0x8F => short_form!(BranchOnBitSet(0), ZeroPageRelative, 3, 5),
+        0x9F => short_form!(BranchOnBitSet(1), ZeroPageRelative, 3, 5),
+        0xAF => short_form!(BranchOnBitSet(2), ZeroPageRelative, 3, 5),
+        0xBF => short_form!(BranchOnBitSet(3), ZeroPageRelative, 3, 5),
+        0xCF => short_form!(BranchOnBitSet(4), ZeroPageRelative, 3, 5),
+        0xDF => short_form!(BranchOnBitSet(5), ZeroPageRelative, 3, 5),
+        0xEF => short_form!(BranchOnBitSet(6), ZeroPageRelative, 3, 5),
+        0xFF => short_form!(BranchOnBitSet(7), ZeroPageRelative, 3, 5),
+        _ => synertek65c02(opcode),
+    }
+}
+
+/// Decodes the opcode like the Synertek 65C02 does.
+///
+/// Only the opcodes that differ from the NMOS table are listed,
+/// every unofficial NMOS opcode turns into a new instruction or a NOP
+pub(crate) const fn synertek65c02(opcode: u8) -> Instruction {
+    match opcode {
+        0x04 => short_form!(TestAndSetBits, ZeroPage, 2, 5),
+        0x0C => short_form!(TestAndSetBits, Absolute, 3, 6),
+        0x14 => short_form!(TestAndResetBits, ZeroPage, 2, 5),
+        0x1C => short_form!(TestAndResetBits, Absolute, 3, 6),
+
+        0x12 => short_form!(BitwiseORAccumulator, ZeroPageIndirect, 2, 5),
+        0x32 => short_form!(BitwiseANDAccumulator, ZeroPageIndirect, 2, 5),
+        0x52 => short_form!(BitwiseXORAccumulator, ZeroPageIndirect, 2, 5),
+        0x72 => short_form!(AddToAccumulator, ZeroPageIndirect, 2, 5),
+        0x92 => short_form!(StoreAccumulator, ZeroPageIndirect, 2, 5),
+        0xB2 => short_form!(LoadToAccumulator, ZeroPageIndirect, 2, 5),
+        0xD2 => short_form!(ComapareWithAccumulator, ZeroPageIndirect, 2, 5),
+        0xF2 => short_form!(SubtractFromAccumulator, ZeroPageIndirect, 2, 5),
+
+        0x1A => short_form!(Increment, Accumulator, 1, 2),
+        0x3A => short_form!(Decrement, Accumulator, 1, 2),
+
+        0x34 => short_form!(TestBitsWithAccumulator, IndexedZeroPageX, 2, 4),
+        0x3C => short_form!(TestBitsWithAccumulator, IndexedAbsoluteX, 3, 4),
+        0x89 => short_form!(TestBitsWithImmediate, Immediate, 2, 2),
+
+        0x5A => short_form!(PushYToStack, Implied, 1, 3),
+        0x7A => short_form!(PullYFromStack, Implied, 1, 4),
+        0xDA => short_form!(PushXToStack, Implied, 1, 3),
+        0xFA => short_form!(PullXFromStack, Implied, 1, 4),
+
+        0x64 => short_form!(StoreZero, ZeroPage, 2, 3),
+        0x74 => short_form!(StoreZero, IndexedZeroPageX, 2, 4),
+        0x9C => short_form!(StoreZero, Absolute, 3, 4),
+        0x9E => short_form!(StoreZero, IndexedAbsoluteX, 3, 5),
+
+        0x80 => short_form!(BranchAlways, Relative, 2, 2),
+
+        // the page wrap bug is fixed at the cost of a cycle
+        0x6C => short_form!(Jump, AbsoluteIndirect, 3, 6),
+        0x7C => short_form!(Jump, AbsoluteIndexedIndirect, 3, 6),
+
+        // shifts and rotates only take the extra cycle when indexing crosses a page
+        0x1E => short_form!(LeftShift, IndexedAbsoluteX, 3, 6),
+        0x3E => short_form!(RotateBitLeft, IndexedAbsoluteX, 3, 6),
+        0x5E => short_form!(RightShift, IndexedAbsoluteX, 3, 6),
+        0x7E => short_form!(RotateBitRight, IndexedAbsoluteX, 3, 6),
+
+        // reserved opcodes, they act as NOPs of various lengths
+        0x02 | 0x22 | 0x42 | 0x62 | 0x82 | 0xC2 | 0xE2 => short_form!(NoOp, Immediate, 2, 2),
+        0x44 => short_form!(NoOp, ZeroPage, 2, 3),
+        0x54 | 0xD4 | 0xF4 => short_form!(NoOp, IndexedZeroPageX, 2, 4),
+        0x5C => short_form!(NoOp, Absolute, 3, 8),
+        0xDC | 0xFC => short_form!(NoOp, Absolute, 3, 4),
+
+        0x03 | 0x13 | 0x23 | 0x33 | 0x43 | 0x53 | 0x63 | 0x73 | 0x83 | 0x93 | 0xA3 | 0xB3
+        | 0xC3 | 0xD3 | 0xE3 | 0xF3 => short_form!(NoOp, Implied, 1, 1),
+        0x07 | 0x17 | 0x27 | 0x37 | 0x47 | 0x57 | 0x67 | 0x77 | 0x87 | 0x97 | 0xA7 | 0xB7
+        | 0xC7 | 0xD7 | 0xE7 | 0xF7 => short_form!(NoOp, Implied, 1, 1),
+        0x0B | 0x1B | 0x2B | 0x3B | 0x4B | 0x5B | 0x6B | 0x7B | 0x8B | 0x9B | 0xAB | 0xBB
+        | 0xCB | 0xDB | 0xEB | 0xFB => short_form!(NoOp, Implied, 1, 1),
+        0x0F | 0x1F | 0x2F | 0x3F | 0x4F | 0x5F | 0x6F | 0x7F | 0x8F | 0x9F | 0xAF | 0xBF
+        | 0xCF | 0xDF | 0xEF | 0xFF => short_form!(NoOp, Implied, 1, 1),
+
+        _ => nmos6502(opcode),
+    }
+}
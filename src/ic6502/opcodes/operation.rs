@@ -1,22 +1,24 @@
-use std::result;
-
 use serde_derive::{Deserialize, Serialize};
 
 use crate::{
-    bus::OpenBus,
-    ic6502::{Flags, IC6502, STACK_PAGE, is_set, set_flag, unset_flag},
+    bus::{BusError, OpenBus},
+    ic6502::{
+        Fault, FlagSet, Flags, IC6502, IRQ_VECTOR, NMI_VECTOR, STACK_PAGE, StatusRegister, Variant,
+        opcodes::page_crossed,
+    },
 };
 
 ///Represents the Location of some Data
 ///
 ///Either a byte in a Register or an Address from where to read/write
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum OperationArgument {
     Value(u8),
     Pointer(u16),
 }
 use OperationArgument::*;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Operation {
     #[serde(rename = "ADC")]
     AddToAccumulator,
@@ -145,21 +147,487 @@ pub enum Operation {
 
     #[serde(rename = "NOP")]
     NoOp,
+
+    // Unofficial operations
+    #[serde(rename = "LAX")]
+    LoadToAccumulatorAndX,
+    #[serde(rename = "LXA")]
+    LoadImmediateToAccumulatorAndX,
+    #[serde(rename = "SAX")]
+    StoreAccumulatorANDX,
+    #[serde(rename = "DCP")]
+    DecrementAndCompare,
+    #[serde(rename = "ISC")]
+    IncrementAndSubtract,
+    #[serde(rename = "SLO")]
+    LeftShiftAndOR,
+    #[serde(rename = "RLA")]
+    RotateBitLeftAndAND,
+    #[serde(rename = "SRE")]
+    RightShiftAndXOR,
+    #[serde(rename = "RRA")]
+    RotateBitRightAndAdd,
+    #[serde(rename = "ANC")]
+    BitwiseANDWithCarry,
+    #[serde(rename = "ALR")]
+    BitwiseANDAndRightShift,
+    #[serde(rename = "ARR")]
+    BitwiseANDAndRotateRight,
+    #[serde(rename = "SBX")]
+    SubtractFromAccumulatorANDX,
+    #[serde(rename = "XAA")]
+    TransferXANDToAccumulator,
+    #[serde(rename = "SHA")]
+    StoreAccumulatorANDXANDHigh,
+    #[serde(rename = "SHX")]
+    StoreXANDHigh,
+    #[serde(rename = "SHY")]
+    StoreYANDHigh,
+    #[serde(rename = "TAS")]
+    TransferAccumulatorANDXToStack,
+    #[serde(rename = "LAS")]
+    LoadANDStackPointer,
+    /// Locks up the CPU until it gets reset
+    #[serde(rename = "JAM")]
+    Jam,
+
+    // 65C02 operations
+    #[serde(rename = "BRA")]
+    BranchAlways,
+    #[serde(rename = "PHX")]
+    PushXToStack,
+    #[serde(rename = "PHY")]
+    PushYToStack,
+    #[serde(rename = "PLX")]
+    PullXFromStack,
+    #[serde(rename = "PLY")]
+    PullYFromStack,
+    #[serde(rename = "STZ")]
+    StoreZero,
+    #[serde(rename = "TRB")]
+    TestAndResetBits,
+    #[serde(rename = "TSB")]
+    TestAndSetBits,
+    /// BIT with an immediate operand only sets the `Zero` flag
+    #[serde(rename = "BIT", skip_deserializing)]
+    TestBitsWithImmediate,
+    /// Stops the clock until an interrupt line gets asserted
+    #[serde(rename = "WAI")]
+    WaitForInterrupt,
+    /// Stops the clock until the CPU gets reset
+    #[serde(rename = "STP")]
+    Stop,
+
+    // Rockwell and WDC bit operations, they carry the number of the bit they work on
+    #[serde(rename = "RMB")]
+    ResetMemoryBit(u8),
+    #[serde(rename = "SMB")]
+    SetMemoryBit(u8),
+    #[serde(rename = "BBR")]
+    BranchOnBitReset(u8),
+    #[serde(rename = "BBS")]
+    BranchOnBitSet(u8),
+}
+
+/// How an operation uses the bus, decides the cycle by cycle sequence it goes through
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OperationKind {
+    /// Reads its operand from the effective address
+    Read,
+    /// Writes to the effective address
+    Write,
+    /// Reads the effective address, writes the result back
+    ReadModifyWrite,
+    /// Only touches registers
+    Implied,
+    Branch,
+    Jump,
+    Push,
+    Pull,
+    JumpToSubRoutine,
+    ReturnFromSubroutine,
+    ReturnFromInterrupt,
+    Break,
+    /// Idles for two cycles, then stops the clock
+    Halt,
+    /// Tests a bit of a zero page byte and branches on it
+    BitBranch,
 }
 
-type OperationResult = Option<Thingimagic>;
+type OperationResult = Result<Thingimagic, Fault>;
 
 pub enum Thingimagic {
     Jump(u16),
+    /// A taken branch, costs extra cycles on top of a regular jump
+    Branch(u16),
     Increment,
 }
 
 use Thingimagic::*;
 
 impl Operation {
+    /// Whether the operation takes an extra cycle when indexed addressing crosses a page boundary.
+    ///
+    /// Only operations that just read their operand are affected,
+    /// stores and read-modify-write operations always take the extra cycle.
+    /// The 65C02 makes an exception for its shifts and rotates
+    pub const fn has_page_penalty<V: Variant>(&self) -> bool {
+        use Operation::*;
+        if V::CMOS
+            && matches!(
+                self,
+                LeftShift | RightShift | RotateBitLeft | RotateBitRight
+            )
+        {
+            return true;
+        }
+        matches!(
+            self,
+            AddToAccumulator
+                | SubtractFromAccumulator
+                | BitwiseANDAccumulator
+                | BitwiseXORAccumulator
+                | BitwiseORAccumulator
+                | ComapareWithAccumulator
+                | LoadToAccumulator
+                | LoadToXRegister
+                | LoadToYRegister
+                | TestBitsWithAccumulator
+                | NoOp
+                | LoadToAccumulatorAndX
+                | LoadANDStackPointer
+        )
+    }
+
+    /// Whether the operation takes an extra cycle for decimal arithmetic,
+    /// the 65C02 needs it to get the flags right
+    pub fn has_decimal_penalty<V: Variant>(&self, cpu: &IC6502<V>) -> bool {
+        use Operation::*;
+        V::CMOS && decimal_mode(cpu) && matches!(self, AddToAccumulator | SubtractFromAccumulator)
+    }
+
+    /// Assembler mnemonic, the bit instructions carry their bit number like `RMB3`
+    pub const fn mnemonic(&self) -> &'static str {
+        use Operation::*;
+        match self {
+            AddToAccumulator => "ADC",
+            SubtractFromAccumulator => "SBC",
+            Increment => "INC",
+            IncrementIndexX => "INX",
+            IncrementIndexY => "INY",
+            Decrement => "DEC",
+            DecrementIndexX => "DEX",
+            DecrementIndexY => "DEY",
+            BitwiseANDAccumulator => "AND",
+            BitwiseXORAccumulator => "EOR",
+            BitwiseORAccumulator => "ORA",
+            LeftShift => "ASL",
+            RightShift => "LSR",
+            RotateBitLeft => "ROL",
+            RotateBitRight => "ROR",
+            BranchOnCarryClear => "BCC",
+            BranchOnCarrySet => "BCS",
+            BranchOnResultZero => "BEQ",
+            BranchOnResultMinus => "BMI",
+            BranchOnResultNotZero => "BNE",
+            BranchOnResultPlus => "BPL",
+            BranchOnOverflowClear => "BVC",
+            BranchOnOverflowSet => "BVS",
+            TestBitsWithAccumulator | TestBitsWithImmediate => "BIT",
+            ClearCarryFlag => "CLC",
+            ClearDecimalMode => "CLD",
+            ClearInterruptDisableBit => "CLI",
+            ClearOverflowFlag => "CLV",
+            ComapareWithAccumulator => "CMP",
+            CompareWithIndexX => "CPX",
+            CompareWithIndexY => "CPY",
+            Jump => "JMP",
+            JumpToSubRoutine => "JSR",
+            LoadToAccumulator => "LDA",
+            LoadToXRegister => "LDX",
+            LoadToYRegister => "LDY",
+            PushAccumulatorToStack => "PHA",
+            PushStatusToStack => "PHP",
+            PullAccumulatorFromStack => "PLA",
+            PullStatusFromStack => "PLP",
+            ReturnFromInterrupt => "RTI",
+            ReturnFromSubroutine => "RTS",
+            SetCarryFlag => "SEC",
+            DetDecimalMode => "SED",
+            SetInterruptStatus => "SEI",
+            StoreAccumulator => "STA",
+            StoreXRegister => "STX",
+            StoreYRegister => "STY",
+            TransferAccumulatorToX => "TAX",
+            TransferAccumulatorToY => "TAY",
+            TransferStackPointerToX => "TSX",
+            TransferXToAccumulator => "TXA",
+            TransferXToStackRegister => "TXS",
+            TransferYToAccumulator => "TYA",
+            ForceBreak => "BRK",
+            NoOp => "NOP",
+            LoadToAccumulatorAndX => "LAX",
+            LoadImmediateToAccumulatorAndX => "LXA",
+            StoreAccumulatorANDX => "SAX",
+            DecrementAndCompare => "DCP",
+            IncrementAndSubtract => "ISC",
+            LeftShiftAndOR => "SLO",
+            RotateBitLeftAndAND => "RLA",
+            RightShiftAndXOR => "SRE",
+            RotateBitRightAndAdd => "RRA",
+            BitwiseANDWithCarry => "ANC",
+            BitwiseANDAndRightShift => "ALR",
+            BitwiseANDAndRotateRight => "ARR",
+            SubtractFromAccumulatorANDX => "SBX",
+            TransferXANDToAccumulator => "XAA",
+            StoreAccumulatorANDXANDHigh => "SHA",
+            StoreXANDHigh => "SHX",
+            StoreYANDHigh => "SHY",
+            TransferAccumulatorANDXToStack => "TAS",
+            LoadANDStackPointer => "LAS",
+            Jam => "JAM",
+            BranchAlways => "BRA",
+            PushXToStack => "PHX",
+            PushYToStack => "PHY",
+            PullXFromStack => "PLX",
+            PullYFromStack => "PLY",
+            StoreZero => "STZ",
+            TestAndResetBits => "TRB",
+            TestAndSetBits => "TSB",
+            WaitForInterrupt => "WAI",
+            Stop => "STP",
+            ResetMemoryBit(bit) => [
+                "RMB0", "RMB1", "RMB2", "RMB3", "RMB4", "RMB5", "RMB6", "RMB7",
+            ][*bit as usize & 7],
+            SetMemoryBit(bit) => [
+                "SMB0", "SMB1", "SMB2", "SMB3", "SMB4", "SMB5", "SMB6", "SMB7",
+            ][*bit as usize & 7],
+            BranchOnBitReset(bit) => [
+                "BBR0", "BBR1", "BBR2", "BBR3", "BBR4", "BBR5", "BBR6", "BBR7",
+            ][*bit as usize & 7],
+            BranchOnBitSet(bit) => [
+                "BBS0", "BBS1", "BBS2", "BBS3", "BBS4", "BBS5", "BBS6", "BBS7",
+            ][*bit as usize & 7],
+        }
+    }
+
+    /// Status flags whose value affects the result of the operation
+    pub const fn flags_read<V: Variant>(&self) -> FlagSet {
+        use Operation::*;
+        let decimal = match V::DECIMAL_MODE {
+            true => Flags::DecimalMode as u8,
+            false => 0,
+        };
+        FlagSet::from_bits(match self {
+            AddToAccumulator
+            | SubtractFromAccumulator
+            | IncrementAndSubtract
+            | RotateBitRightAndAdd
+            | BitwiseANDAndRotateRight => Flags::Carry as u8 | decimal,
+            RotateBitLeft | RotateBitRight | RotateBitLeftAndAND => Flags::Carry as u8,
+            BranchOnCarryClear | BranchOnCarrySet => Flags::Carry as u8,
+            BranchOnResultZero | BranchOnResultNotZero => Flags::Zero as u8,
+            BranchOnResultMinus | BranchOnResultPlus => Flags::Negative as u8,
+            BranchOnOverflowClear | BranchOnOverflowSet => Flags::Overflow as u8,
+            PushStatusToStack | ForceBreak => STATUS_FLAGS,
+            _ => 0,
+        })
+    }
+
+    /// Status flags the operation may change
+    pub const fn flags_written<V: Variant>(&self) -> FlagSet {
+        use Operation::*;
+        const NZ: u8 = Flags::Negative as u8 | Flags::Zero as u8;
+        const NZC: u8 = NZ | Flags::Carry as u8;
+        const NVZC: u8 = NZC | Flags::Overflow as u8;
+        FlagSet::from_bits(match self {
+            AddToAccumulator
+            | SubtractFromAccumulator
+            | IncrementAndSubtract
+            | RotateBitRightAndAdd
+            | BitwiseANDAndRotateRight => NVZC,
+            LeftShift
+            | RightShift
+            | RotateBitLeft
+            | RotateBitRight
+            | ComapareWithAccumulator
+            | CompareWithIndexX
+            | CompareWithIndexY
+            | DecrementAndCompare
+            | LeftShiftAndOR
+            | RotateBitLeftAndAND
+            | RightShiftAndXOR
+            | BitwiseANDWithCarry
+            | BitwiseANDAndRightShift
+            | SubtractFromAccumulatorANDX => NZC,
+            Increment
+            | IncrementIndexX
+            | IncrementIndexY
+            | Decrement
+            | DecrementIndexX
+            | DecrementIndexY
+            | BitwiseANDAccumulator
+            | BitwiseXORAccumulator
+            | BitwiseORAccumulator
+            | LoadToAccumulator
+            | LoadToXRegister
+            | LoadToYRegister
+            | PullAccumulatorFromStack
+            | PullXFromStack
+            | PullYFromStack
+            | TransferAccumulatorToX
+            | TransferAccumulatorToY
+            | TransferStackPointerToX
+            | TransferXToAccumulator
+            | TransferYToAccumulator
+            | LoadToAccumulatorAndX
+            | LoadImmediateToAccumulatorAndX
+            | TransferXANDToAccumulator
+            | LoadANDStackPointer => NZ,
+            TestBitsWithAccumulator => NZ | Flags::Overflow as u8,
+            TestBitsWithImmediate | TestAndResetBits | TestAndSetBits => Flags::Zero as u8,
+            ClearCarryFlag | SetCarryFlag => Flags::Carry as u8,
+            ClearDecimalMode | DetDecimalMode => Flags::DecimalMode as u8,
+            ClearInterruptDisableBit | SetInterruptStatus => Flags::InterruptDisable as u8,
+            ClearOverflowFlag => Flags::Overflow as u8,
+            PullStatusFromStack | ReturnFromInterrupt => STATUS_FLAGS,
+            ForceBreak if V::CMOS => Flags::InterruptDisable as u8 | Flags::DecimalMode as u8,
+            ForceBreak => Flags::InterruptDisable as u8,
+            _ => 0,
+        })
+    }
+
+    /// Whether the manufacturer documents the opcode,
+    /// the NOPs besides `$EA` and the second SBC at `$EB` are left overs of the decoder
+    pub const fn is_official(&self, opcode: u8) -> bool {
+        use Operation::*;
+        match self {
+            NoOp => opcode == 0xEA,
+            LoadToAccumulatorAndX
+            | LoadImmediateToAccumulatorAndX
+            | StoreAccumulatorANDX
+            | DecrementAndCompare
+            | IncrementAndSubtract
+            | LeftShiftAndOR
+            | RotateBitLeftAndAND
+            | RightShiftAndXOR
+            | RotateBitRightAndAdd
+            | BitwiseANDWithCarry
+            | BitwiseANDAndRightShift
+            | BitwiseANDAndRotateRight
+            | SubtractFromAccumulatorANDX
+            | TransferXANDToAccumulator
+            | StoreAccumulatorANDXANDHigh
+            | StoreXANDHigh
+            | StoreYANDHigh
+            | TransferAccumulatorANDXToStack
+            | LoadANDStackPointer
+            | Jam => false,
+            _ => opcode != 0xEB,
+        }
+    }
+
+    /// CLI, SEI and PLP change `InterruptDisable` after the interrupts were polled,
+    /// so the change only affects interrupts after the next instruction
+    pub const fn delays_interrupts(&self) -> bool {
+        use Operation::*;
+        matches!(
+            self,
+            ClearInterruptDisableBit | SetInterruptStatus | PullStatusFromStack
+        )
+    }
+
+    #[inline(always)]
+    pub fn kind(&self) -> OperationKind {
+        use Operation::*;
+        match self {
+            AddToAccumulator
+            | SubtractFromAccumulator
+            | BitwiseANDAccumulator
+            | BitwiseXORAccumulator
+            | BitwiseORAccumulator
+            | TestBitsWithAccumulator
+            | ComapareWithAccumulator
+            | CompareWithIndexX
+            | CompareWithIndexY
+            | LoadToAccumulator
+            | LoadToXRegister
+            | LoadToYRegister
+            | NoOp
+            | LoadToAccumulatorAndX
+            | LoadImmediateToAccumulatorAndX
+            | BitwiseANDWithCarry
+            | BitwiseANDAndRightShift
+            | BitwiseANDAndRotateRight
+            | SubtractFromAccumulatorANDX
+            | TransferXANDToAccumulator
+            | LoadANDStackPointer
+            | TestBitsWithImmediate => OperationKind::Read,
+
+            StoreAccumulator
+            | StoreXRegister
+            | StoreYRegister
+            | StoreAccumulatorANDX
+            | StoreAccumulatorANDXANDHigh
+            | StoreXANDHigh
+            | StoreYANDHigh
+            | TransferAccumulatorANDXToStack
+            | StoreZero => OperationKind::Write,
+
+            Increment | Decrement | LeftShift | RightShift | RotateBitLeft | RotateBitRight
+            | DecrementAndCompare | IncrementAndSubtract | LeftShiftAndOR | RotateBitLeftAndAND
+            | RightShiftAndXOR | RotateBitRightAndAdd | TestAndResetBits | TestAndSetBits
+            | ResetMemoryBit(_) | SetMemoryBit(_) => OperationKind::ReadModifyWrite,
+
+            IncrementIndexX
+            | IncrementIndexY
+            | DecrementIndexX
+            | DecrementIndexY
+            | ClearCarryFlag
+            | ClearDecimalMode
+            | ClearInterruptDisableBit
+            | ClearOverflowFlag
+            | SetCarryFlag
+            | DetDecimalMode
+            | SetInterruptStatus
+            | TransferAccumulatorToX
+            | TransferAccumulatorToY
+            | TransferStackPointerToX
+            | TransferXToAccumulator
+            | TransferXToStackRegister
+            | TransferYToAccumulator
+            | Jam => OperationKind::Implied,
+
+            BranchOnCarryClear
+            | BranchOnCarrySet
+            | BranchOnResultZero
+            | BranchOnResultNotZero
+            | BranchOnResultMinus
+            | BranchOnResultPlus
+            | BranchOnOverflowClear
+            | BranchOnOverflowSet
+            | BranchAlways => OperationKind::Branch,
+
+            Jump => OperationKind::Jump,
+            PushAccumulatorToStack | PushStatusToStack | PushXToStack | PushYToStack => {
+                OperationKind::Push
+            }
+            PullAccumulatorFromStack | PullStatusFromStack | PullXFromStack | PullYFromStack => {
+                OperationKind::Pull
+            }
+            JumpToSubRoutine => OperationKind::JumpToSubRoutine,
+            ReturnFromSubroutine => OperationKind::ReturnFromSubroutine,
+            ReturnFromInterrupt => OperationKind::ReturnFromInterrupt,
+            ForceBreak => OperationKind::Break,
+            WaitForInterrupt | Stop => OperationKind::Halt,
+            BranchOnBitReset(_) | BranchOnBitSet(_) => OperationKind::BitBranch,
+        }
+    }
+
+    #[inline(always)]
     pub fn run(
         &self,
-        cpu: &mut IC6502,
+        cpu: &mut IC6502<impl Variant>,
         bus: &mut impl OpenBus,
         arg: OperationArgument,
     ) -> OperationResult {
@@ -221,14 +689,77 @@ impl Operation {
             TransferYToAccumulator => operation_tya(cpu, bus, arg),
             ForceBreak => operation_brk(cpu, bus, arg),
             NoOp => operation_nop(cpu, bus, arg),
+            LoadToAccumulatorAndX => operation_lax(cpu, bus, arg),
+            LoadImmediateToAccumulatorAndX => operation_lxa(cpu, bus, arg),
+            StoreAccumulatorANDX => operation_sax(cpu, bus, arg),
+            DecrementAndCompare => operation_dcp(cpu, bus, arg),
+            IncrementAndSubtract => operation_isc(cpu, bus, arg),
+            LeftShiftAndOR => operation_slo(cpu, bus, arg),
+            RotateBitLeftAndAND => operation_rla(cpu, bus, arg),
+            RightShiftAndXOR => operation_sre(cpu, bus, arg),
+            RotateBitRightAndAdd => operation_rra(cpu, bus, arg),
+            BitwiseANDWithCarry => operation_anc(cpu, bus, arg),
+            BitwiseANDAndRightShift => operation_alr(cpu, bus, arg),
+            BitwiseANDAndRotateRight => operation_arr(cpu, bus, arg),
+            SubtractFromAccumulatorANDX => operation_sbx(cpu, bus, arg),
+            TransferXANDToAccumulator => operation_xaa(cpu, bus, arg),
+            StoreAccumulatorANDXANDHigh => operation_sha(cpu, bus, arg),
+            StoreXANDHigh => operation_shx(cpu, bus, arg),
+            StoreYANDHigh => operation_shy(cpu, bus, arg),
+            TransferAccumulatorANDXToStack => operation_tas(cpu, bus, arg),
+            LoadANDStackPointer => operation_las(cpu, bus, arg),
+            Jam => operation_jam(cpu, bus, arg),
+            BranchAlways => operation_bra(cpu, bus, arg),
+            PushXToStack => operation_phx(cpu, bus, arg),
+            PushYToStack => operation_phy(cpu, bus, arg),
+            PullXFromStack => operation_plx(cpu, bus, arg),
+            PullYFromStack => operation_ply(cpu, bus, arg),
+            StoreZero => operation_stz(cpu, bus, arg),
+            TestAndResetBits => operation_trb(cpu, bus, arg),
+            TestAndSetBits => operation_tsb(cpu, bus, arg),
+            TestBitsWithImmediate => operation_bit_imm(cpu, bus, arg),
+            WaitForInterrupt => operation_wai(cpu, bus, arg),
+            Stop => operation_stp(cpu, bus, arg),
+            ResetMemoryBit(bit) => operation_rmb(cpu, bus, arg, *bit),
+            SetMemoryBit(bit) => operation_smb(cpu, bus, arg, *bit),
+            BranchOnBitReset(bit) => operation_bbr(cpu, bus, arg, *bit),
+            BranchOnBitSet(bit) => operation_bbs(cpu, bus, arg, *bit),
         }
     }
 }
 
+/// The flags that live in the status register, `Break` and `Unused` only exist on the stack
+const STATUS_FLAGS: u8 = Flags::Negative as u8
+    | Flags::Overflow as u8
+    | Flags::DecimalMode as u8
+    | Flags::InterruptDisable as u8
+    | Flags::Zero as u8
+    | Flags::Carry as u8;
+
+/// Reads the operand of a read-modify-write operation.
+///
+/// The NMOS chips write the unmodified value back while they work on it,
+/// the 65C02 reads it a second time instead
+#[inline(always)]
+fn read_modify<V: Variant>(bus: &mut impl OpenBus, ptr: u16) -> Result<u8, BusError> {
+    let value = bus.read(ptr)?;
+    match V::CMOS {
+        true => bus.read(ptr).map(|_| ())?,
+        false => bus.write(ptr, value)?,
+    }
+    Ok(value)
+}
+
+/// Whether ADC and SBC work on binary-coded decimals
+#[inline(always)]
+fn decimal_mode<V: Variant>(cpu: &IC6502<V>) -> bool {
+    V::DECIMAL_MODE && cpu.status.contains(Flags::DecimalMode)
+}
+
 // Operations
 #[inline(always)]
-fn operation_adc(
-    cpu: &mut IC6502,
+fn operation_adc<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -237,29 +768,56 @@ fn operation_adc(
         Pointer(p) => bus.read(p)?,
     };
 
-    let (result, overflow1) = cpu
-        .accumulator
-        .overflowing_add(is_set!(cpu.status, Carry) as u8);
+    let carry = cpu.status.contains(Flags::Carry) as u8;
+    let (result, overflow1) = cpu.accumulator.overflowing_add(carry);
 
     let (result, overflow2) = result.overflowing_add(value);
 
-    set_flag!(cpu.status, Carry, overflow1 || overflow2);
-    set_flag!(cpu.status, Zero, result == 0);
-    set_flag!(cpu.status, Negative, is_set!(result, Negative));
+    cpu.status.set(Flags::Carry, overflow1 || overflow2);
+    cpu.status.set(Flags::Zero, result == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(result));
     //(result ^ A) & (result ^ memory) & $80
-    set_flag!(
-        cpu.status,
-        Overflow,
-        ((result ^ cpu.accumulator) & (result ^ value) & 0x80) == 0x80
+    cpu.status.set(
+        Flags::Overflow,
+        ((result ^ cpu.accumulator) & (result ^ value) & 0x80) == 0x80,
     );
 
+    if decimal_mode(cpu) {
+        // the NMOS 6502 keeps Z from the binary sum
+        // and takes N and V before the high digit got adjusted
+        let mut low = (cpu.accumulator & 0x0F) + (value & 0x0F) + carry;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let sum = (cpu.accumulator & 0xF0) as u16 + (value & 0xF0) as u16 + low as u16;
+        let signed =
+            (cpu.accumulator & 0xF0) as i8 as i16 + (value & 0xF0) as i8 as i16 + low as i16;
+
+        cpu.status
+            .set(Flags::Negative, Flags::Negative.is_set(sum as u8));
+        cpu.status
+            .set(Flags::Overflow, !(-128..=127).contains(&signed));
+
+        let sum = if sum >= 0xA0 { sum + 0x60 } else { sum };
+        cpu.status.set(Flags::Carry, sum >= 0x100);
+
+        cpu.accumulator = sum as u8;
+        if V::CMOS {
+            cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+            cpu.status
+                .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
+        }
+        return Ok(Increment);
+    }
+
     cpu.accumulator = result;
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_sbc(
-    cpu: &mut IC6502,
+fn operation_sbc<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -268,90 +826,161 @@ fn operation_sbc(
         Pointer(p) => bus.read(p)?,
     };
 
+    let carry = cpu.status.contains(Flags::Carry) as u8;
     let (result, overflow1) = cpu.accumulator.overflowing_add(!value);
-    let (result, overflow2) = result.overflowing_add(is_set!(cpu.status, Carry) as u8);
+    let (result, overflow2) = result.overflowing_add(carry);
 
-    set_flag!(cpu.status, Carry, overflow1 || overflow2);
-    set_flag!(cpu.status, Zero, result == 0);
-    set_flag!(cpu.status, Negative, is_set!(result, Negative));
+    cpu.status.set(Flags::Carry, overflow1 || overflow2);
+    cpu.status.set(Flags::Zero, result == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(result));
     //(result ^ A) & (result ^ memory) & $80
-    set_flag!(
-        cpu.status,
-        Overflow,
-        ((result ^ cpu.accumulator) & (result ^ !value) & 0x80) == 0x80
+    cpu.status.set(
+        Flags::Overflow,
+        ((result ^ cpu.accumulator) & (result ^ !value) & 0x80) == 0x80,
     );
 
+    if decimal_mode(cpu) {
+        // the flags stay the ones of the binary difference on the NMOS 6502,
+        // the 65C02 adjusts the whole difference and sets N and Z from it
+        let mut low = (cpu.accumulator & 0x0F) as i16 - (value & 0x0F) as i16 + carry as i16 - 1;
+        let difference = match V::CMOS {
+            true => {
+                let mut difference = cpu.accumulator as i16 - value as i16 + carry as i16 - 1;
+                if difference < 0 {
+                    difference -= 0x60;
+                }
+                if low < 0 {
+                    difference -= 0x06;
+                }
+                difference
+            }
+            false => {
+                if low < 0 {
+                    low = ((low - 0x06) & 0x0F) - 0x10;
+                }
+                let mut difference = (cpu.accumulator & 0xF0) as i16 - (value & 0xF0) as i16 + low;
+                if difference < 0 {
+                    difference -= 0x60;
+                }
+                difference
+            }
+        };
+
+        cpu.accumulator = difference as u8;
+        if V::CMOS {
+            cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+            cpu.status
+                .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
+        }
+        return Ok(Increment);
+    }
+
     cpu.accumulator = result;
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_inc(
-    cpu: &mut IC6502,
+fn operation_inc<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
-    let Pointer(ptr) = argument else {
-        return None;
-    };
-    let value = bus.read(ptr)?.wrapping_add(1);
-    bus.write(ptr, value)?;
-    set_flag!(cpu.status, Zero, value == 0);
-    set_flag!(cpu.status, Negative, is_set!(value, Negative));
-    Some(Increment)
+    let value = match argument {
+        Value(v) => v,
+        Pointer(p) => read_modify::<V>(bus, p)?,
+    }
+    .wrapping_add(1);
+
+    match argument {
+        Value(_) => cpu.accumulator = value,
+        Pointer(p) => bus.write(p, value)?,
+    }
+
+    cpu.status.set(Flags::Zero, value == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(value));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_inx(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_inx(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.register_x = cpu.register_x.wrapping_add(1);
-    set_flag!(cpu.status, Zero, cpu.register_x == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.register_x, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.register_x == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_x));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_iny(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_iny(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.register_y = cpu.register_y.wrapping_add(1);
-    set_flag!(cpu.status, Zero, cpu.register_y == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.register_y, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.register_y == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_y));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_dec(
-    cpu: &mut IC6502,
+fn operation_dec<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
-    let Pointer(ptr) = argument else {
-        return None;
-    };
-    let value = bus.read(ptr)?.wrapping_sub(1);
-    bus.write(ptr, value)?;
-    set_flag!(cpu.status, Zero, value == 0);
-    set_flag!(cpu.status, Negative, is_set!(value, Negative));
-    Some(Increment)
+    let value = match argument {
+        Value(v) => v,
+        Pointer(p) => read_modify::<V>(bus, p)?,
+    }
+    .wrapping_sub(1);
+
+    match argument {
+        Value(_) => cpu.accumulator = value,
+        Pointer(p) => bus.write(p, value)?,
+    }
+
+    cpu.status.set(Flags::Zero, value == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(value));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_dex(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_dex(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.register_x = cpu.register_x.wrapping_sub(1);
-    set_flag!(cpu.status, Zero, cpu.register_x == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.register_x, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.register_x == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_x));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_dey(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_dey(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.register_y = cpu.register_y.wrapping_sub(1);
-    set_flag!(cpu.status, Zero, cpu.register_y == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.register_y, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.register_y == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_y));
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_and(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -362,15 +991,16 @@ fn operation_and(
 
     cpu.accumulator &= value;
 
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_eor(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -381,16 +1011,17 @@ fn operation_eor(
 
     cpu.accumulator ^= value;
 
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
 
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_ora(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -401,141 +1032,145 @@ fn operation_ora(
 
     cpu.accumulator |= value;
 
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
 
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_asl(
-    cpu: &mut IC6502,
+fn operation_asl<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let value = match argument {
         Value(v) => v,
-        Pointer(p) => bus.read(p)?,
+        Pointer(p) => read_modify::<V>(bus, p)?,
     };
 
-    set_flag!(cpu.status, Carry, is_set!(value, Negative));
+    cpu.status.set(Flags::Carry, Flags::Negative.is_set(value));
 
     let value = value << 1;
 
-    set_flag!(cpu.status, Negative, is_set!(value, Negative));
-    set_flag!(cpu.status, Zero, value == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(value));
+    cpu.status.set(Flags::Zero, value == 0);
 
     match argument {
         Value(_) => cpu.accumulator = value,
         Pointer(p) => bus.write(p, value)?,
     }
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_lsr(
-    cpu: &mut IC6502,
+fn operation_lsr<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let value = match argument {
         Value(v) => v,
-        Pointer(p) => bus.read(p)?,
+        Pointer(p) => read_modify::<V>(bus, p)?,
     };
 
-    set_flag!(cpu.status, Carry, value & 1 == 1);
+    cpu.status.set(Flags::Carry, value & 1 == 1);
 
     let value = value >> 1;
 
-    unset_flag!(cpu.status, Negative);
-    set_flag!(cpu.status, Zero, value == 0);
+    cpu.status.remove(Flags::Negative);
+    cpu.status.set(Flags::Zero, value == 0);
 
     match argument {
         Value(_) => cpu.accumulator = value,
         Pointer(p) => bus.write(p, value)?,
     }
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_rol(
-    cpu: &mut IC6502,
+fn operation_rol<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let value = match argument {
         Value(v) => v,
-        Pointer(p) => bus.read(p)?,
+        Pointer(p) => read_modify::<V>(bus, p)?,
     };
 
-    let carry_status = is_set!(cpu.status, Carry) as u8; //get current carry
+    let carry_status = cpu.status.contains(Flags::Carry) as u8; //get current carry
 
-    set_flag!(cpu.status, Carry, is_set!(value, Negative)); //shift bit 7 into carry
+    cpu.status.set(Flags::Carry, Flags::Negative.is_set(value)); //shift bit 7 into carry
 
     let value = (value << 1) | carry_status;
 
-    set_flag!(cpu.status, Negative, is_set!(value, Negative));
-    set_flag!(cpu.status, Zero, value == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(value));
+    cpu.status.set(Flags::Zero, value == 0);
 
     match argument {
         Value(_) => cpu.accumulator = value,
         Pointer(p) => bus.write(p, value)?,
     }
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_ror(
-    cpu: &mut IC6502,
+fn operation_ror<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let value = match argument {
         Value(v) => v,
-        Pointer(p) => bus.read(p)?,
+        Pointer(p) => read_modify::<V>(bus, p)?,
     };
 
-    let carry_status = is_set!(cpu.status, Carry) as u8; //get current carry
+    let carry_status = cpu.status.contains(Flags::Carry) as u8; //get current carry
     let carry_status = carry_status.rotate_right(1); //shift least significant bit to most significant
 
-    set_flag!(cpu.status, Carry, is_set!(value, Carry)); //shift bit 7 into carr, Negativey
+    cpu.status.set(Flags::Carry, Flags::Carry.is_set(value)); //shift bit 0 into carry
 
     let value = (value >> 1) | carry_status;
 
-    set_flag!(cpu.status, Negative, is_set!(value, Negative));
-    set_flag!(cpu.status, Zero, value == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(value));
+    cpu.status.set(Flags::Zero, value == 0);
 
     match argument {
         Value(_) => cpu.accumulator = value,
         Pointer(p) => bus.write(p, value)?,
     }
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 macro_rules! branch_relative {
     ($ident:ident, $flag:ident) => {
-        branch_relative!($ident, cpu, is_set!(cpu.status, $flag), $flag);
+        branch_relative!($ident, cpu, cpu.status.contains(Flags::$flag));
     };
     ($ident:ident, !$flag:ident) => {
-        branch_relative!($ident, cpu, !is_set!(cpu.status, $flag), $flag);
+        branch_relative!($ident, cpu, !cpu.status.contains(Flags::$flag));
     };
-    ($ident:ident, $cpu:ident, $expr:expr, $flag:ident) => {
+    ($ident:ident, $cpu:ident, $expr:expr) => {
         fn $ident(
-            $cpu: &mut IC6502,
+            $cpu: &mut IC6502<impl Variant>,
             _: &mut impl OpenBus,
             argument: OperationArgument,
         ) -> OperationResult {
             if !$expr {
-                return Some(Increment);
+                return Ok(Increment);
             };
 
             let Value(value) = argument else {
-                return None;
+                return Err(Fault::InvalidArgument(argument));
             };
 
             let value = value.cast_signed() as i16;
@@ -543,8 +1178,8 @@ macro_rules! branch_relative {
             let location = $cpu.program_counter.wrapping_add(2);
 
             match value.is_negative() {
-                true => Some(Jump(location.wrapping_sub(value.unsigned_abs()))),
-                false => Some(Jump(location.wrapping_add(value.unsigned_abs()))),
+                true => Ok(Branch(location.wrapping_sub(value.unsigned_abs()))),
+                false => Ok(Branch(location.wrapping_add(value.unsigned_abs()))),
             }
         }
     };
@@ -562,9 +1197,11 @@ branch_relative!(operation_bpl, !Negative);
 branch_relative!(operation_bvs, Overflow);
 branch_relative!(operation_bvc, !Overflow);
 
+branch_relative!(operation_bra, cpu, true);
+
 #[inline(always)]
 fn operation_bit(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -573,43 +1210,61 @@ fn operation_bit(
         Pointer(p) => bus.read(p)?,
     };
 
-    set_flag!(cpu.status, Overflow, is_set!(value, Overflow));
-    set_flag!(cpu.status, Negative, is_set!(value, Negative));
+    cpu.status
+        .set(Flags::Overflow, Flags::Overflow.is_set(value));
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(value));
 
     let value = value & cpu.accumulator;
 
-    set_flag!(cpu.status, Zero, value == 0);
+    cpu.status.set(Flags::Zero, value == 0);
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_clc(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    unset_flag!(cpu.status, Carry);
-    Some(Increment)
+fn operation_clc(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.status.remove(Flags::Carry);
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_cld(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    unset_flag!(cpu.status, DecimalMode);
-    Some(Increment)
+fn operation_cld(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.status.remove(Flags::DecimalMode);
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_cli(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    unset_flag!(cpu.status, InterruptDisable);
-    Some(Increment)
+fn operation_cli(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.status.remove(Flags::InterruptDisable);
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_clv(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    unset_flag!(cpu.status, Overflow);
-    Some(Increment)
+fn operation_clv(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.status.remove(Flags::Overflow);
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_cmp(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -620,16 +1275,17 @@ fn operation_cmp(
 
     let result = cpu.accumulator.wrapping_sub(value);
 
-    set_flag!(cpu.status, Carry, cpu.accumulator >= value);
-    set_flag!(cpu.status, Zero, cpu.accumulator == value);
-    set_flag!(cpu.status, Negative, is_set!(result, Negative));
+    cpu.status.set(Flags::Carry, cpu.accumulator >= value);
+    cpu.status.set(Flags::Zero, cpu.accumulator == value);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(result));
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_cpx(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -640,16 +1296,17 @@ fn operation_cpx(
 
     let result = cpu.register_x.wrapping_sub(value);
 
-    set_flag!(cpu.status, Carry, cpu.register_x >= value);
-    set_flag!(cpu.status, Zero, cpu.register_x == value);
-    set_flag!(cpu.status, Negative, is_set!(result, Negative));
+    cpu.status.set(Flags::Carry, cpu.register_x >= value);
+    cpu.status.set(Flags::Zero, cpu.register_x == value);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(result));
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_cpy(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -660,48 +1317,59 @@ fn operation_cpy(
 
     let result = cpu.register_y.wrapping_sub(value);
 
-    set_flag!(cpu.status, Carry, cpu.register_y >= value);
-    set_flag!(cpu.status, Zero, cpu.register_y == value);
-    set_flag!(cpu.status, Negative, is_set!(result, Negative));
+    cpu.status.set(Flags::Carry, cpu.register_y >= value);
+    cpu.status.set(Flags::Zero, cpu.register_y == value);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(result));
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_jmp(
-    _: &mut IC6502,
+    _: &mut IC6502<impl Variant>,
     _: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let Pointer(value) = argument else {
-        return None;
+        return Err(Fault::InvalidArgument(argument));
     };
     //implement hardware bug if jump location is across pages
 
-    Some(Jump(value))
+    Ok(Jump(value))
 }
 
+/// Expects a pointer to the operand, the return address gets pushed
+/// in between reading the low and the high byte of the target
 #[inline(always)]
 fn operation_jsr(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
-    let Pointer(addr) = argument else {
-        return None;
+    let Pointer(operand) = argument else {
+        return Err(Fault::InvalidArgument(argument));
     };
 
+    let target_low_byte = bus.read(operand)?;
+    bus.read(STACK_PAGE.wrapping_add(cpu.stack_pointer as u16))?;
+
     let [low_byte, high_byte] = cpu.program_counter.wrapping_add(2).to_le_bytes();
 
-    operation_pha(cpu, bus, Value(high_byte));
-    operation_pha(cpu, bus, Value(low_byte));
+    operation_pha(cpu, bus, Value(high_byte))?;
+    operation_pha(cpu, bus, Value(low_byte))?;
 
-    Some(Jump(addr))
+    let target_high_byte = bus.read(operand.wrapping_add(1))?;
+
+    Ok(Jump(u16::from_le_bytes([
+        target_low_byte,
+        target_high_byte,
+    ])))
 }
 
 #[inline(always)]
 fn operation_lda(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -710,14 +1378,15 @@ fn operation_lda(
         Pointer(ptr) => bus.read(ptr)?,
     };
     cpu.accumulator = value;
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_ldx(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -726,14 +1395,15 @@ fn operation_ldx(
         Pointer(ptr) => bus.read(ptr)?,
     };
     cpu.register_x = value;
-    set_flag!(cpu.status, Zero, cpu.register_x == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.register_x, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.register_x == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_x));
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_ldy(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
@@ -742,237 +1412,855 @@ fn operation_ldy(
         Pointer(ptr) => bus.read(ptr)?,
     };
     cpu.register_y = value;
-    set_flag!(cpu.status, Zero, cpu.register_y == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.register_y, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.register_y == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_y));
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_pha(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let Value(value) = argument else {
-        return None;
+        return Err(Fault::InvalidArgument(argument));
     };
 
     let stack_addr = STACK_PAGE.wrapping_add(cpu.stack_pointer as u16);
     bus.write(stack_addr, value)?;
     cpu.stack_pointer = cpu.stack_pointer.wrapping_sub(1);
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_php(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     _: OperationArgument,
 ) -> OperationResult {
     let addr = (cpu.stack_pointer as u16).wrapping_add(0x0100);
 
-    bus.write(addr, cpu.status | Flags::Break);
+    bus.write(addr, cpu.status.to_pushed(true))?;
 
     cpu.stack_pointer = cpu.stack_pointer.wrapping_sub(1);
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_pla(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     _: OperationArgument,
 ) -> OperationResult {
     cpu.stack_pointer = cpu.stack_pointer.wrapping_add(1);
     cpu.accumulator = bus.read(cpu.stack_pointer as u16 + 0x0100)?;
 
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_plp(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     _: OperationArgument,
 ) -> OperationResult {
     cpu.stack_pointer = cpu.stack_pointer.wrapping_add(1);
-    let status = bus.read(cpu.stack_pointer as u16 + 0x0100)? & !Flags::Break;
-    cpu.status = status | Flags::Unused;
+    cpu.status = StatusRegister::from_pulled(bus.read(cpu.stack_pointer as u16 + 0x0100)?);
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_rti(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     _: OperationArgument,
 ) -> OperationResult {
     operation_plp(cpu, bus, Value(cpu.accumulator))?;
-    let Jump(pc) = operation_rts(cpu, bus, Value(cpu.accumulator))? else {
-        return None; //unreachable
-    };
+    let low_byte = pull(cpu, bus)?;
+    let high_byte = pull(cpu, bus)?;
 
-    Some(Jump(pc.wrapping_sub(1))) //rts increments pc by one, rti does not
+    Ok(Jump(u16::from_le_bytes([low_byte, high_byte])))
 }
 
+/// Reads the pulled return address once more while incrementing it
 #[inline(always)]
 fn operation_rts(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     _: OperationArgument,
 ) -> OperationResult {
-    let accumulator = cpu.accumulator;
-    let status = cpu.status;
-
-    operation_pla(cpu, bus, Value(accumulator));
-
-    let low_byte = cpu.accumulator;
+    let low_byte = pull(cpu, bus)?;
+    let high_byte = pull(cpu, bus)?;
 
-    operation_pla(cpu, bus, Value(accumulator));
+    let location = u16::from_le_bytes([low_byte, high_byte]);
+    bus.read(location)?;
 
-    let high_byte = cpu.accumulator;
-
-    cpu.accumulator = accumulator;
-    cpu.status = status;
-
-    let location = u16::from_le_bytes([low_byte, high_byte]).wrapping_add(1);
+    Ok(Jump(location.wrapping_add(1)))
+}
 
-    Some(Jump(location))
+#[inline(always)]
+fn pull(cpu: &mut IC6502<impl Variant>, bus: &mut impl OpenBus) -> Result<u8, BusError> {
+    cpu.stack_pointer = cpu.stack_pointer.wrapping_add(1);
+    bus.read(STACK_PAGE.wrapping_add(cpu.stack_pointer as u16))
 }
 
 #[inline(always)]
-fn operation_sec(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    set_flag!(cpu.status, Carry);
-    Some(Increment)
+fn operation_sec(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.status.insert(Flags::Carry);
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_sed(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    set_flag!(cpu.status, DecimalMode);
-    Some(Increment)
+fn operation_sed(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.status.insert(Flags::DecimalMode);
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_sei(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    set_flag!(cpu.status, InterruptDisable);
-    Some(Increment)
+fn operation_sei(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.status.insert(Flags::InterruptDisable);
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_sta(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let Pointer(addr) = argument else {
-        return None;
+        return Err(Fault::InvalidArgument(argument));
     };
 
     bus.write(addr, cpu.accumulator)?;
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_stx(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let Pointer(addr) = argument else {
-        return None;
+        return Err(Fault::InvalidArgument(argument));
     };
 
     bus.write(addr, cpu.register_x)?;
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
 fn operation_sty(
-    cpu: &mut IC6502,
+    cpu: &mut IC6502<impl Variant>,
     bus: &mut impl OpenBus,
     argument: OperationArgument,
 ) -> OperationResult {
     let Pointer(addr) = argument else {
-        return None;
+        return Err(Fault::InvalidArgument(argument));
     };
 
     bus.write(addr, cpu.register_y)?;
 
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_tax(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_tax(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.register_x = cpu.accumulator;
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_tay(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_tay(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.register_y = cpu.accumulator;
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_tsx(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_tsx(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.register_x = cpu.stack_pointer;
-    set_flag!(cpu.status, Zero, cpu.register_x == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.register_x, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.register_x == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_x));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_txa(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_txa(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.accumulator = cpu.register_x;
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_txs(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_txs(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.stack_pointer = cpu.register_x;
-    Some(Increment)
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_tya(cpu: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
+fn operation_tya(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
     cpu.accumulator = cpu.register_y;
-    set_flag!(cpu.status, Zero, cpu.accumulator == 0);
-    set_flag!(cpu.status, Negative, is_set!(cpu.accumulator, Negative));
-    Some(Increment)
+    cpu.status.set(Flags::Zero, cpu.accumulator == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.accumulator));
+    Ok(Increment)
 }
 
 #[inline(always)]
-fn operation_brk(
-    cpu: &mut IC6502,
+fn operation_brk<V: Variant>(
+    cpu: &mut IC6502<V>,
     bus: &mut impl OpenBus,
     _: OperationArgument,
 ) -> OperationResult {
-    let _ = bus.read(cpu.program_counter.wrapping_add(1));
     let [low_byte, high_byte] = (cpu.program_counter.wrapping_add(2)).to_le_bytes();
     operation_pha(cpu, bus, Value(high_byte))?;
     operation_pha(cpu, bus, Value(low_byte))?;
-    operation_pha(cpu, bus, Value(cpu.status | Flags::Break))?;
-    cpu.status |= Flags::InterruptDisable;
-    Some(Jump(u16::from_le_bytes([
-        bus.read(0xFFFE)?,
-        bus.read(0xFFFF)?,
+    operation_pha(cpu, bus, Value(cpu.status.to_pushed(true)))?;
+    cpu.status.insert(Flags::InterruptDisable);
+    if V::CMOS {
+        cpu.status.remove(Flags::DecimalMode);
+    }
+    let vector = match cpu.take_nmi() {
+        true => NMI_VECTOR,
+        false => IRQ_VECTOR,
+    };
+    Ok(Jump(u16::from_le_bytes([
+        bus.read(vector)?,
+        bus.read(vector.wrapping_add(1))?,
     ])))
 }
 
+/// NOPs with an operand still read it
+#[inline(always)]
+fn operation_nop(
+    _: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    if let Pointer(ptr) = argument {
+        bus.read(ptr)?;
+    }
+    Ok(Increment)
+}
+
+// Unofficial Operations
+
+#[inline(always)]
+fn operation_lax(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    operation_lda(cpu, bus, argument)?;
+    cpu.register_x = cpu.accumulator;
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_lxa(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let value = match argument {
+        Value(v) => v,
+        Pointer(ptr) => bus.read(ptr)?,
+    };
+    let value = (cpu.accumulator | cpu.unstable.lxa_magic) & value;
+    operation_lax(cpu, bus, Value(value))
+}
+
+#[inline(always)]
+fn operation_sax(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(addr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+
+    bus.write(addr, cpu.accumulator & cpu.register_x)?;
+
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_dcp<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?.wrapping_sub(1);
+    bus.write(ptr, value)?;
+    operation_cmp(cpu, bus, Value(value))
+}
+
+#[inline(always)]
+fn operation_isc<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?.wrapping_add(1);
+    bus.write(ptr, value)?;
+    operation_sbc(cpu, bus, Value(value))
+}
+
+#[inline(always)]
+fn operation_slo<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?;
+
+    cpu.status.set(Flags::Carry, Flags::Negative.is_set(value));
+    let value = value << 1;
+
+    bus.write(ptr, value)?;
+    operation_ora(cpu, bus, Value(value))
+}
+
+#[inline(always)]
+fn operation_rla<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?;
+
+    let carry_status = cpu.status.contains(Flags::Carry) as u8;
+    cpu.status.set(Flags::Carry, Flags::Negative.is_set(value));
+    let value = (value << 1) | carry_status;
+
+    bus.write(ptr, value)?;
+    operation_and(cpu, bus, Value(value))
+}
+
+#[inline(always)]
+fn operation_sre<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?;
+
+    cpu.status.set(Flags::Carry, value & 1 == 1);
+    let value = value >> 1;
+
+    bus.write(ptr, value)?;
+    operation_eor(cpu, bus, Value(value))
+}
+
+#[inline(always)]
+fn operation_rra<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?;
+
+    let carry_status = (cpu.status.contains(Flags::Carry) as u8).rotate_right(1);
+    cpu.status.set(Flags::Carry, value & 1 == 1);
+    let value = (value >> 1) | carry_status;
+
+    bus.write(ptr, value)?;
+    operation_adc(cpu, bus, Value(value))
+}
+
+#[inline(always)]
+fn operation_anc(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    operation_and(cpu, bus, argument)?;
+    cpu.status
+        .set(Flags::Carry, Flags::Negative.is_set(cpu.accumulator));
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_alr(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    operation_and(cpu, bus, argument)?;
+    operation_lsr(cpu, bus, Value(cpu.accumulator))
+}
+
+#[inline(always)]
+fn operation_arr(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    operation_and(cpu, bus, argument)?;
+
+    let carry_status = (cpu.status.contains(Flags::Carry) as u8).rotate_right(1);
+    let value = (cpu.accumulator >> 1) | carry_status;
+
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(value));
+    cpu.status.set(Flags::Zero, value == 0);
+
+    if decimal_mode(cpu) {
+        // the adder fixes up each digit of the rotated value like after an addition
+        let and = cpu.accumulator;
+        cpu.status
+            .set(Flags::Overflow, Flags::Overflow.is_set(and ^ value));
+
+        let mut value = value;
+        if (and & 0x0F) + (and & 0x01) > 0x05 {
+            value = (value & 0xF0) | (value.wrapping_add(0x06) & 0x0F);
+        }
+        let high_carry = (and & 0xF0) as u16 + (and & 0x10) as u16 > 0x50;
+        if high_carry {
+            value = (value & 0x0F) | (value.wrapping_add(0x60) & 0xF0);
+        }
+        cpu.status.set(Flags::Carry, high_carry);
+
+        cpu.accumulator = value;
+        return Ok(Increment);
+    }
+    // carry and overflow come out of the adder instead of the shifter
+    cpu.status.set(Flags::Carry, Flags::Overflow.is_set(value));
+    cpu.status
+        .set(Flags::Overflow, ((value >> 6) ^ (value >> 5)) & 1 == 1);
+
+    cpu.accumulator = value;
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_sbx(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let value = match argument {
+        Value(v) => v,
+        Pointer(ptr) => bus.read(ptr)?,
+    };
+
+    let and = cpu.accumulator & cpu.register_x;
+    let result = and.wrapping_sub(value);
+
+    cpu.status.set(Flags::Carry, and >= value);
+    cpu.status.set(Flags::Zero, result == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(result));
+
+    cpu.register_x = result;
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_xaa(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let value = match argument {
+        Value(v) => v,
+        Pointer(ptr) => bus.read(ptr)?,
+    };
+    let value = (cpu.accumulator | cpu.unstable.xaa_magic) & cpu.register_x & value;
+    operation_lda(cpu, bus, Value(value))
+}
+
+/// Stores a register ANDed with the high byte of the base address plus one.
+///
+/// The value gets on the bus while the CPU is still fixing up the high byte of the address,
+/// so when indexing crossed a page the stored value ends up as the high byte of the address.
+/// [`UnstableOpcodes`](crate::ic6502::UnstableOpcodes) can turn either quirk off
+#[inline(always)]
+fn store_and_high(
+    cpu: &IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    addr: u16,
+    index: u8,
+    register: u8,
+) -> OperationResult {
+    let base = addr.wrapping_sub(index as u16);
+    let [_, high_byte] = base.to_le_bytes();
+    let value = match cpu.unstable.sh_and_high {
+        true => register & high_byte.wrapping_add(1),
+        false => register,
+    };
+
+    let addr = match page_crossed(base, addr) && cpu.unstable.sh_page_cross_corrupts {
+        true => u16::from_le_bytes([addr as u8, value]),
+        false => addr,
+    };
+
+    bus.write(addr, value)?;
+    Ok(Increment)
+}
+
 #[inline(always)]
-fn operation_nop(_: &mut IC6502, _: &mut impl OpenBus, _: OperationArgument) -> OperationResult {
-    Some(Increment)
+fn operation_sha(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(addr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    store_and_high(
+        cpu,
+        bus,
+        addr,
+        cpu.register_y,
+        cpu.accumulator & cpu.register_x,
+    )
+}
+
+#[inline(always)]
+fn operation_shx(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(addr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    store_and_high(cpu, bus, addr, cpu.register_y, cpu.register_x)
+}
+
+#[inline(always)]
+fn operation_shy(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(addr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    store_and_high(cpu, bus, addr, cpu.register_x, cpu.register_y)
+}
+
+#[inline(always)]
+fn operation_tas(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(addr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    cpu.stack_pointer = cpu.accumulator & cpu.register_x;
+    store_and_high(cpu, bus, addr, cpu.register_y, cpu.stack_pointer)
+}
+
+#[inline(always)]
+fn operation_las(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let value = match argument {
+        Value(v) => v,
+        Pointer(ptr) => bus.read(ptr)?,
+    };
+    cpu.stack_pointer &= value;
+    operation_lax(cpu, bus, Value(cpu.stack_pointer))
+}
+
+/// Never finishes, the program counter stays on the opcode until the CPU gets reset
+#[inline(always)]
+fn operation_jam(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.jammed = true;
+    Err(Fault::Jammed)
+}
+
+// 65C02 Operations
+
+#[inline(always)]
+fn operation_phx(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    operation_pha(cpu, bus, Value(cpu.register_x))
+}
+
+#[inline(always)]
+fn operation_phy(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    operation_pha(cpu, bus, Value(cpu.register_y))
+}
+
+#[inline(always)]
+fn operation_plx(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.stack_pointer = cpu.stack_pointer.wrapping_add(1);
+    cpu.register_x = bus.read(cpu.stack_pointer as u16 + 0x0100)?;
+
+    cpu.status.set(Flags::Zero, cpu.register_x == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_x));
+
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_ply(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.stack_pointer = cpu.stack_pointer.wrapping_add(1);
+    cpu.register_y = bus.read(cpu.stack_pointer as u16 + 0x0100)?;
+
+    cpu.status.set(Flags::Zero, cpu.register_y == 0);
+    cpu.status
+        .set(Flags::Negative, Flags::Negative.is_set(cpu.register_y));
+
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_stz(
+    _: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(addr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+
+    bus.write(addr, 0)?;
+
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_trb<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?;
+    cpu.status.set(Flags::Zero, value & cpu.accumulator == 0);
+    bus.write(ptr, value & !cpu.accumulator)?;
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_tsb<V: Variant>(
+    cpu: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)?;
+    cpu.status.set(Flags::Zero, value & cpu.accumulator == 0);
+    bus.write(ptr, value | cpu.accumulator)?;
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_bit_imm(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+) -> OperationResult {
+    let value = match argument {
+        Value(v) => v,
+        Pointer(p) => bus.read(p)?,
+    };
+
+    cpu.status.set(Flags::Zero, value & cpu.accumulator == 0);
+
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_wai(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.waiting = true;
+    Ok(Increment)
+}
+
+/// Halts like the JAM opcodes, the program counter stays on the opcode until the CPU gets reset
+#[inline(always)]
+fn operation_stp(
+    cpu: &mut IC6502<impl Variant>,
+    _: &mut impl OpenBus,
+    _: OperationArgument,
+) -> OperationResult {
+    cpu.jammed = true;
+    Err(Fault::Jammed)
+}
+
+// Rockwell and WDC bit Operations
+
+#[inline(always)]
+fn operation_rmb<V: Variant>(
+    _: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+    bit: u8,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)? & !(1 << bit);
+    bus.write(ptr, value)?;
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_smb<V: Variant>(
+    _: &mut IC6502<V>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+    bit: u8,
+) -> OperationResult {
+    let Pointer(ptr) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    let value = read_modify::<V>(bus, ptr)? | (1 << bit);
+    bus.write(ptr, value)?;
+    Ok(Increment)
+}
+
+#[inline(always)]
+fn operation_bbr(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+    bit: u8,
+) -> OperationResult {
+    let Value(value) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    branch_on_bit(cpu, bus, value & (1 << bit) == 0)
+}
+
+#[inline(always)]
+fn operation_bbs(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    argument: OperationArgument,
+    bit: u8,
+) -> OperationResult {
+    let Value(value) = argument else {
+        return Err(Fault::InvalidArgument(argument));
+    };
+    branch_on_bit(cpu, bus, value & (1 << bit) != 0)
+}
+
+/// The branch offset is the last byte of BBR and BBS,
+/// it counts from the end of the three byte instruction
+#[inline(always)]
+fn branch_on_bit(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+    taken: bool,
+) -> OperationResult {
+    let offset = bus.read(cpu.program_counter.wrapping_add(2))?;
+    if !taken {
+        return Ok(Increment);
+    }
+
+    let location = cpu.program_counter.wrapping_add(3);
+    Ok(Branch(
+        location.wrapping_add_signed(offset.cast_signed() as i16),
+    ))
 }
@@ -0,0 +1,121 @@
+use crate::ic6502::{
+    FlagSet,
+    opcodes::{AdressingMode, Instruction, Operation},
+};
+
+/// Everything there is to know about an opcode without executing it.
+///
+/// Every [`Variant`](crate::ic6502::Variant) has a table of these in
+/// [`Variant::OPCODES`](crate::ic6502::Variant::OPCODES), indexed by the opcode
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct OpcodeInfo {
+    pub opcode: u8,
+    pub mnemonic: &'static str,
+    pub operation: Operation,
+    pub addressing_mode: AdressingMode,
+    /// Length of the instruction including the opcode
+    pub bytes: u8,
+    /// Cycles the instruction takes without any penalty, 0 for the JAM opcodes
+    pub cycles: u8,
+    /// Whether the instruction takes another cycle when indexing crosses a page.
+    ///
+    /// Only read instructions, and the shifts and rotates of the 65C02, skip that cycle
+    /// when they stay on the page, everything else always takes it and is `false` here.
+    /// The cycles of taken branches and of decimal ADC and SBC on the 65C02 aren't included
+    pub page_penalty: bool,
+    /// [`Flags`](crate::ic6502::Flags) that affect the result
+    pub flags_read: FlagSet,
+    /// [`Flags`](crate::ic6502::Flags) that may get changed
+    pub flags_written: FlagSet,
+    /// Whether the opcode is documented by the manufacturer
+    pub official: bool,
+}
+
+impl OpcodeInfo {
+    pub const fn instruction(&self) -> Instruction {
+        Instruction {
+            operation: self.operation,
+            addressing_mode: self.addressing_mode,
+            bytes: self.bytes,
+            cycles: self.cycles,
+        }
+    }
+}
+
+/// Builds the table of a variant out of its decoder at compile time
+macro_rules! opcode_table {
+    ($variant:ty, $decode:path) => {{
+        use $crate::ic6502::opcodes::{AdressingMode, Instruction, OpcodeInfo, Operation};
+
+        let mut table = [OpcodeInfo {
+            opcode: 0,
+            mnemonic: "",
+            operation: Operation::NoOp,
+            addressing_mode: AdressingMode::Implied,
+            bytes: 0,
+            cycles: 0,
+            page_penalty: false,
+            flags_read: $crate::ic6502::FlagSet::EMPTY,
+            flags_written: $crate::ic6502::FlagSet::EMPTY,
+            official: false,
+        }; 256];
+
+        let mut index = 0;
+        while index < table.len() {
+            let opcode = index as u8;
+            let Instruction {
+                operation,
+                addressing_mode,
+                bytes,
+                cycles,
+            } = $decode(opcode);
+
+            table[index] = OpcodeInfo {
+                opcode,
+                mnemonic: operation.mnemonic(),
+                operation,
+                addressing_mode,
+                bytes,
+                cycles,
+                page_penalty: operation.has_page_penalty::<$variant>()
+                    && matches!(
+                        addressing_mode,
+                        AdressingMode::IndexedAbsoluteX
+                            | AdressingMode::IndexedAbsoluteY
+                            | AdressingMode::IndirectIndexed
+                    ),
+                flags_read: operation.flags_read::<$variant>(),
+                flags_written: operation.flags_written::<$variant>(),
+                official: operation.is_official(opcode),
+            };
+            index += 1;
+        }
+
+        table
+    }};
+}
+pub(crate) use opcode_table;
+
+#[cfg(test)]
+mod tests {
+    use crate::ic6502::{Flags, Nmos6502, Ricoh2A03, Variant, Wdc65C02};
+
+    #[test]
+    fn flags_read_and_written() {
+        let nop = Ricoh2A03::OPCODES[0xEA];
+        assert!(nop.flags_read.is_empty());
+        assert!(nop.flags_written.is_empty());
+
+        // ADC #imm reads the decimal flag only where it works
+        assert_eq!(Ricoh2A03::OPCODES[0x69].flags_read.bits(), 0x01);
+        assert_eq!(Nmos6502::OPCODES[0x69].flags_read.bits(), 0x09);
+        assert_eq!(Nmos6502::OPCODES[0x69].flags_written.bits(), 0xC3);
+
+        // PHP and PLP move every flag but the ones that only exist on the stack
+        for info in [Nmos6502::OPCODES[0x08], Wdc65C02::OPCODES[0x28]] {
+            let flags = info.flags_read.bits() | info.flags_written.bits();
+            assert_eq!(flags, 0xCF);
+            assert!(!info.flags_read.contains(Flags::Unused));
+        }
+    }
+}
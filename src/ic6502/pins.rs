@@ -0,0 +1,206 @@
+use crate::{
+    bus::{BusError, OpenBus},
+    ic6502::{Flags, IC6502, Variant, stepped::NextAccess},
+};
+
+/// State of the SO and RDY inputs of the CPU
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub(super) struct Pins {
+    so: bool,
+    /// The CPU stalls while RDY is pulled low
+    rdy_low: bool,
+    stalled_cycles: u64,
+}
+
+impl<V: Variant> IC6502<V> {
+    /// Sets the level of the SO pin, asserting it sets the `Overflow` flag
+    pub fn set_so(&mut self, asserted: bool) {
+        if asserted && !self.pins.so {
+            self.status.insert(Flags::Overflow);
+        }
+        self.pins.so = asserted;
+    }
+
+    /// Sets the level of the RDY line.
+    ///
+    /// While it is low the CPU stalls on its next read cycle and keeps repeating that read,
+    /// the 65C02 stalls on write cycles as well. Cycles spent like that are counted
+    /// in [`IC6502::stalled_cycles`]
+    pub fn set_ready(&mut self, ready: bool) {
+        self.pins.rdy_low = !ready;
+    }
+
+    /// Whether RDY is high and lets the CPU run
+    pub fn ready(&self) -> bool {
+        !self.pins.rdy_low
+    }
+
+    /// Whether the next cycle is spent stalled,
+    /// RDY is low and the access the CPU is about to make is one it stalls on
+    pub fn stalled(&self) -> bool {
+        self.pins.rdy_low && self.stalls_on(self.next_access())
+    }
+
+    /// Cycles the CPU spent stalled by RDY so far
+    pub fn stalled_cycles(&self) -> u64 {
+        self.pins.stalled_cycles
+    }
+
+    /// Spends the upcoming cycle stalled if RDY is low and the chip stalls on its access,
+    /// returns whether it did.
+    ///
+    /// Stalled reads still reach the bus, so the registers behind it see them
+    #[inline(always)]
+    pub(super) fn stall(&mut self, bus: &mut impl OpenBus) -> Result<bool, BusError> {
+        match self.pins.rdy_low {
+            true => self.stall_on_next_access(bus),
+            false => Ok(false),
+        }
+    }
+
+    #[cold]
+    fn stall_on_next_access(&mut self, bus: &mut impl OpenBus) -> Result<bool, BusError> {
+        let access = self.next_access();
+        if !self.stalls_on(access) {
+            return Ok(false);
+        }
+
+        if let NextAccess::Read(addr) = access {
+            bus.read(addr)?;
+        }
+        self.pins.stalled_cycles += 1;
+        Ok(true)
+    }
+
+    /// Only reads stall the NMOS chips, the 65C02 stalls on writes as well
+    fn stalls_on(&self, access: NextAccess) -> bool {
+        match access {
+            NextAccess::Read(_) => true,
+            NextAccess::Write => V::CMOS,
+            NextAccess::Idle => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bus::{AccessKind, BusAccess, FlatMemory, TracingBus},
+        ic6502::{Mos6507, Nmos6502, Ricoh2A03, Rockwell65C02, Synertek65C02, Wdc65C02},
+    };
+
+    /// Ticks once and returns what reached the bus
+    fn tick<V: Variant>(cpu: &mut IC6502<V>, memory: &mut FlatMemory) -> Vec<BusAccess> {
+        let mut bus = TracingBus::new(memory);
+        let _ = cpu.tick(&mut bus);
+        bus.into_log()
+    }
+
+    /// Runs random bytes with the interrupt lines wiggling,
+    /// checking what every tick accesses against the prediction
+    fn predict_random_programs<V: Variant>() {
+        let mut seed = 0x2545_F491_4F6C_DD1Du64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        let mut memory = FlatMemory::new();
+        memory.as_mut_slice().fill_with(|| random() as u8);
+        let mut cpu = IC6502::<V>::builder()
+            .program_counter(random() as u16)
+            .build();
+
+        for _ in 0..200_000 {
+            match random() % 64 {
+                0 => cpu.set_irq(random() % 2 == 0),
+                1 => cpu.set_nmi(random() % 2 == 0),
+                2 if cpu.jammed().is_some() => cpu.reset(),
+                _ => {}
+            }
+
+            let predicted = cpu.next_access();
+            let log = tick(&mut cpu, &mut memory);
+            match (predicted, log.first()) {
+                (NextAccess::Read(addr), Some(access)) => {
+                    assert_eq!(access.kind, AccessKind::Read);
+                    assert_eq!(access.addr, addr & V::ADDRESS_MASK);
+                }
+                (NextAccess::Write, Some(access)) => assert_eq!(access.kind, AccessKind::Write),
+                (NextAccess::Idle, None) => {}
+                (predicted, access) => panic!("predicted {predicted:?}, got {access:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn predicts_every_access() {
+        predict_random_programs::<Nmos6502>();
+        predict_random_programs::<Ricoh2A03>();
+        predict_random_programs::<Mos6507>();
+        predict_random_programs::<Wdc65C02>();
+        predict_random_programs::<Rockwell65C02>();
+        predict_random_programs::<Synertek65C02>();
+    }
+
+    /// `program` at `$0200`, ticked up to its last cycle before pulling RDY low
+    fn before_last_cycle<V: Variant>(program: &[u8], cycles: u8) -> (IC6502<V>, FlatMemory) {
+        let mut memory = FlatMemory::new();
+        memory.load(0x0200, program);
+        let mut cpu = IC6502::<V>::builder().program_counter(0x0200).build();
+        for _ in 1..cycles {
+            tick(&mut cpu, &mut memory);
+        }
+        cpu.set_ready(false);
+        (cpu, memory)
+    }
+
+    #[test]
+    fn stalls_on_reads() {
+        // LDA $10
+        let (mut cpu, mut memory) = before_last_cycle::<Nmos6502>(&[0xA5, 0x10], 3);
+        assert!(cpu.stalled());
+        for _ in 0..3 {
+            let log = tick(&mut cpu, &mut memory);
+            assert_eq!(log.len(), 1);
+            assert_eq!((log[0].addr, log[0].kind), (0x0010, AccessKind::Read));
+        }
+        assert_eq!(cpu.stalled_cycles(), 3);
+        assert_eq!(cpu.program_counter(), 0x0200);
+
+        cpu.set_ready(true);
+        assert!(!cpu.stalled());
+        tick(&mut cpu, &mut memory);
+        assert_eq!(cpu.program_counter(), 0x0202);
+    }
+
+    #[test]
+    fn nmos_runs_through_writes() {
+        // STA $10
+        let (mut cpu, mut memory) = before_last_cycle::<Nmos6502>(&[0x85, 0x10], 3);
+        assert!(!cpu.ready());
+        assert!(!cpu.stalled());
+        let log = tick(&mut cpu, &mut memory);
+        assert_eq!((log[0].addr, log[0].kind), (0x0010, AccessKind::Write));
+        assert_eq!(cpu.stalled_cycles(), 0);
+        assert_eq!(cpu.program_counter(), 0x0202);
+
+        // and stalls on the fetch right after
+        assert!(cpu.stalled());
+        let log = tick(&mut cpu, &mut memory);
+        assert_eq!((log[0].addr, log[0].kind), (0x0202, AccessKind::Read));
+        assert_eq!(cpu.stalled_cycles(), 1);
+    }
+
+    #[test]
+    fn cmos_stalls_on_writes() {
+        let (mut cpu, mut memory) = before_last_cycle::<Wdc65C02>(&[0x85, 0x10], 3);
+        assert!(cpu.stalled());
+        assert!(tick(&mut cpu, &mut memory).is_empty());
+        assert_eq!(cpu.stalled_cycles(), 1);
+        assert_eq!(cpu.program_counter(), 0x0200);
+    }
+}
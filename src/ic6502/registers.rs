@@ -0,0 +1,210 @@
+use std::marker::PhantomData;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::ic6502::{
+    Flags, IC6502, Ricoh2A03, StatusRegister, UnstableOpcodes, Variant, stepped::InFlight,
+};
+
+/// Snapshot of the programmer visible registers of a [`IC6502`].
+///
+/// Uses the same field names as the CPU when serialized, so it fits save states and test cases
+#[derive(Debug, Copy, Clone, Default, Deserialize, Serialize, Eq, PartialEq)]
+pub struct Registers {
+    #[serde(rename = "a")]
+    pub accumulator: u8,
+    #[serde(rename = "x")]
+    pub register_x: u8,
+    #[serde(rename = "y")]
+    pub register_y: u8,
+    #[serde(rename = "s")]
+    pub stack_pointer: u8,
+    #[serde(rename = "pc")]
+    pub program_counter: u16,
+    #[serde(rename = "p")]
+    pub status: StatusRegister,
+}
+
+impl<V: Variant> IC6502<V> {
+    pub fn builder() -> IC6502Builder<V> {
+        IC6502Builder::default()
+    }
+
+    pub fn accumulator(&self) -> u8 {
+        self.accumulator
+    }
+
+    pub fn register_x(&self) -> u8 {
+        self.register_x
+    }
+
+    pub fn register_y(&self) -> u8 {
+        self.register_y
+    }
+
+    pub fn stack_pointer(&self) -> u8 {
+        self.stack_pointer
+    }
+
+    /// Address of the next instruction, or of the current one while it is ticked through
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    pub fn status(&self) -> StatusRegister {
+        self.status
+    }
+
+    pub fn set_accumulator(&mut self, value: u8) {
+        self.accumulator = value;
+    }
+
+    pub fn set_register_x(&mut self, value: u8) {
+        self.register_x = value;
+    }
+
+    pub fn set_register_y(&mut self, value: u8) {
+        self.register_y = value;
+    }
+
+    pub fn set_stack_pointer(&mut self, value: u8) {
+        self.stack_pointer = value;
+    }
+
+    /// Continues execution at `addr`.
+    ///
+    /// An instruction that is halfway ticked through gets dropped,
+    /// the next tick fetches the opcode at the new address
+    pub fn set_program_counter(&mut self, addr: u16) {
+        self.program_counter = addr;
+        self.in_flight = InFlight::default();
+    }
+
+    pub fn set_status(&mut self, status: StatusRegister) {
+        self.status = status;
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            accumulator: self.accumulator,
+            register_x: self.register_x,
+            register_y: self.register_y,
+            stack_pointer: self.stack_pointer,
+            program_counter: self.program_counter,
+            status: self.status,
+        }
+    }
+
+    /// Restores all registers at once, see [`IC6502::set_program_counter`]
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.accumulator = registers.accumulator;
+        self.register_x = registers.register_x;
+        self.register_y = registers.register_y;
+        self.stack_pointer = registers.stack_pointer;
+        self.set_program_counter(registers.program_counter);
+        self.status = registers.status;
+    }
+}
+
+impl<V: Variant> From<Registers> for IC6502<V> {
+    fn from(registers: Registers) -> Self {
+        let mut cpu = Self::default();
+        cpu.set_registers(registers);
+        cpu
+    }
+}
+
+/// Sets up a [`IC6502`] in code.
+///
+/// Starts out like the chip after its reset sequence ran with all registers cleared:
+/// the stack pointer at `$FD` and interrupts disabled
+#[derive(Debug, Copy, Clone)]
+pub struct IC6502Builder<V: Variant = Ricoh2A03> {
+    registers: Registers,
+    unstable: UnstableOpcodes,
+    variant: PhantomData<V>,
+}
+
+impl<V: Variant> Default for IC6502Builder<V> {
+    fn default() -> Self {
+        Self {
+            registers: Registers {
+                stack_pointer: 0xFD,
+                status: StatusRegister::from_bits(Flags::InterruptDisable | Flags::Unused),
+                ..Default::default()
+            },
+            unstable: UnstableOpcodes::default(),
+            variant: PhantomData,
+        }
+    }
+}
+
+impl<V: Variant> IC6502Builder<V> {
+    pub fn accumulator(mut self, value: u8) -> Self {
+        self.registers.accumulator = value;
+        self
+    }
+
+    pub fn register_x(mut self, value: u8) -> Self {
+        self.registers.register_x = value;
+        self
+    }
+
+    pub fn register_y(mut self, value: u8) -> Self {
+        self.registers.register_y = value;
+        self
+    }
+
+    pub fn stack_pointer(mut self, value: u8) -> Self {
+        self.registers.stack_pointer = value;
+        self
+    }
+
+    pub fn program_counter(mut self, addr: u16) -> Self {
+        self.registers.program_counter = addr;
+        self
+    }
+
+    pub fn status(mut self, status: StatusRegister) -> Self {
+        self.registers.status = status;
+        self
+    }
+
+    /// Replaces every register, like restoring a snapshot
+    pub fn registers(mut self, registers: Registers) -> Self {
+        self.registers = registers;
+        self
+    }
+
+    pub fn unstable_opcodes(mut self, unstable: UnstableOpcodes) -> Self {
+        self.unstable = unstable;
+        self
+    }
+
+    pub fn build(self) -> IC6502<V> {
+        let mut cpu: IC6502<V> = self.registers.into();
+        cpu.unstable = self.unstable;
+        cpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ic6502::IC6502;
+
+    #[test]
+    fn cpus_compare_by_registers() {
+        let cpu: IC6502 = IC6502::builder().accumulator(0x42).build();
+        let mut other = IC6502::builder()
+            .accumulator(0x42)
+            .unstable_opcodes(UnstableOpcodes::NES)
+            .build();
+        other.set_ready(false);
+        other.set_irq(true);
+        assert_eq!(cpu, other);
+
+        other.set_register_x(1);
+        assert_ne!(cpu, other);
+    }
+}
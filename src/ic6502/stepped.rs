@@ -0,0 +1,1047 @@
+use crate::{
+    bus::{BusError, OpenBus},
+    ic6502::{
+        AddressLines, CpuError, Execution, Fault, Flags, Hook, IC6502, IRQ_VECTOR, Interrupt,
+        NMI_VECTOR, RESET_VECTOR, STACK_PAGE, Variant,
+        opcodes::{
+            AdressingMode, Instruction, Operation, OperationArgument::*, OperationKind,
+            Thingimagic, page_crossed,
+        },
+    },
+};
+
+/// State of the instruction that is currently being executed cycle by cycle
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub(super) struct InFlight {
+    /// Cycle of the instruction that ran last, 0 if the next tick fetches a new opcode
+    cycle: u8,
+    /// Cycle on which the operation itself started, after the effective address was resolved
+    data_start: u8,
+    opcode: u8,
+    /// Effective address, assembled over multiple cycles
+    address: u16,
+    /// Hardware interrupt whose sequence is running instead of an instruction
+    interrupt: Option<Interrupt>,
+    /// Zero page pointer of the indirect addressing modes
+    pointer: u8,
+    /// Data byte that was read on an earlier cycle
+    data: u8,
+    page_crossed: bool,
+    /// What polling the interrupts saw on the previous cycle
+    polled: bool,
+    /// What polling the interrupts saw on the opcode fetch
+    fetch_polled: bool,
+    /// Whether an NMI took over the vector of BRK or an IRQ
+    hijacked: bool,
+}
+
+/// Address a taken branch reads while it fixes up the high byte of its target
+fn fixed_up(next: u16, target: u16) -> u16 {
+    (next & 0xFF00) | (target & 0x00FF)
+}
+
+/// Whether the instruction works on an address that has to be resolved first,
+/// jumps only have theirs once they are done
+fn has_effective_address(kind: OperationKind, addressing_mode: AdressingMode) -> bool {
+    match kind {
+        OperationKind::Read | OperationKind::Write | OperationKind::ReadModifyWrite => !matches!(
+            addressing_mode,
+            AdressingMode::Implied | AdressingMode::Accumulator
+        ),
+        OperationKind::Jump => true,
+        _ => false,
+    }
+}
+
+/// What the next tick does on the bus.
+///
+/// Writes come without their address, some instructions only find it out while they run
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(super) enum NextAccess {
+    Read(u16),
+    Write,
+    /// Jammed or waiting for an interrupt
+    Idle,
+}
+
+/// Progress of the current instruction after a tick
+enum Step {
+    Pending,
+    Done(Thingimagic),
+}
+use Step::*;
+
+/// Single byte stand-in for the bus.
+///
+/// Lets operations run against data that was already transferred on an earlier cycle
+/// and captures what they write, so the write can happen on its own cycle
+struct Latch {
+    addr: u16,
+    value: u8,
+}
+
+impl Latch {
+    fn new(addr: u16, value: u8) -> Self {
+        Self { addr, value }
+    }
+}
+
+impl OpenBus for Latch {
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
+        match addr == self.addr {
+            true => Ok(self.value),
+            false => Err(BusError::read(addr)),
+        }
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
+        self.addr = addr;
+        self.value = byte;
+        Ok(())
+    }
+}
+
+impl<V: Variant> IC6502<V> {
+    /// Advances the CPU by a single clock cycle, doing exactly one bus access.
+    ///
+    /// Returns whether the current instruction finished on this cycle.
+    /// The program counter keeps pointing at the opcode until the instruction is done.
+    /// While waiting for an interrupt or stalled on a write the cycles pass without any bus access
+    pub fn tick(&mut self, bus: &mut impl OpenBus) -> Result<bool, CpuError> {
+        self.tick_with_hooks(bus, &mut ())
+    }
+
+    /// Advances by a single clock cycle like [`IC6502::tick`] and shows the instructions to the hooks.
+    ///
+    /// They see the same as through [`IC6502::cycle_with_hooks`], spread over the cycles:
+    /// `before` on the cycle the operand got resolved on and `after` on the last cycle
+    pub fn tick_with_hooks(
+        &mut self,
+        bus: &mut impl OpenBus,
+        hooks: &mut impl Hook<V>,
+    ) -> Result<bool, CpuError> {
+        let bus = &mut AddressLines::<_, V>::new(bus);
+        let program_counter = self.program_counter;
+
+        let result = match self.stall(bus) {
+            Ok(true) => Ok(false),
+            Ok(false) => self.advance(bus, hooks),
+            Err(bus_error) => Err(bus_error.into()),
+        };
+
+        result.map_err(|fault| {
+            // the opcode is known once it was fetched, unless an interrupt replaced it
+            let fetched = self.in_flight.cycle > 0;
+            CpuError {
+                program_counter,
+                opcode: (fetched && self.in_flight.interrupt.is_none())
+                    .then_some(self.in_flight.opcode),
+                fault,
+            }
+        })
+    }
+
+    pub(super) fn advance(
+        &mut self,
+        bus: &mut impl OpenBus,
+        hooks: &mut impl Hook<V>,
+    ) -> Result<bool, Fault> {
+        if self.reset_pending() {
+            self.in_flight = InFlight::default();
+        } else if self.jammed {
+            return Err(Fault::Jammed);
+        }
+
+        if self.waiting {
+            if !self.interrupt_asserted() {
+                return Ok(false);
+            }
+            self.waiting = false;
+            self.set_interrupt_due(self.poll_interrupt());
+        }
+
+        // every cycle polls the interrupts, an instruction looks at what the cycle
+        // before its last one saw
+        let polled = self.poll_interrupt();
+        let previous = std::mem::replace(&mut self.in_flight.polled, polled);
+
+        if self.in_flight.cycle == 0 {
+            let opcode = bus.read(self.program_counter)?;
+
+            if let Some(interrupt) = self.take_interrupt() {
+                // the opcode that was just fetched gets dropped
+                self.in_flight = InFlight {
+                    cycle: 1,
+                    interrupt: Some(interrupt),
+                    ..Default::default()
+                };
+                return Ok(false);
+            }
+
+            let Instruction {
+                operation,
+                addressing_mode,
+                bytes,
+                cycles,
+            } = V::decode(opcode);
+
+            self.in_flight = InFlight {
+                cycle: 1,
+                opcode,
+                polled,
+                fetch_polled: polled,
+                ..Default::default()
+            };
+
+            // the single cycle NOPs of the 65C02 are done with the fetch
+            if cycles == 1 {
+                let execution = self.execution(None);
+                hooks.before(self, &execution);
+                self.program_counter = self.program_counter.wrapping_add(bytes as u16);
+                self.set_interrupt_due(polled);
+                self.in_flight = InFlight::default();
+                hooks.after(self, &execution, 1);
+                return Ok(true);
+            }
+
+            // instructions without an effective address have nothing to resolve
+            if !has_effective_address(operation.kind(), addressing_mode) {
+                hooks.before(self, &self.execution(None));
+            }
+            return Ok(false);
+        }
+
+        self.in_flight.cycle += 1;
+
+        if let Some(interrupt) = self.in_flight.interrupt {
+            let Done(result) = self.tick_break(bus, Some(interrupt))? else {
+                return Ok(false);
+            };
+            return Ok(self.finish(result, 0, previous));
+        }
+
+        let Instruction {
+            operation,
+            addressing_mode,
+            bytes,
+            cycles,
+        } = V::decode(self.in_flight.opcode);
+
+        let step = match operation.kind() {
+            OperationKind::Read | OperationKind::ReadModifyWrite
+                if matches!(
+                    addressing_mode,
+                    AdressingMode::Implied | AdressingMode::Accumulator
+                ) =>
+            {
+                self.tick_implied(bus, &operation)?
+            }
+            kind
+            @ (OperationKind::Read | OperationKind::Write | OperationKind::ReadModifyWrite) => {
+                if self.in_flight.data_start == 0 {
+                    let skips_fixup = operation.has_page_penalty::<V>();
+                    if self.tick_address(bus, &addressing_mode, skips_fixup)? {
+                        return Ok(false);
+                    }
+                    self.in_flight.data_start = self.in_flight.cycle;
+                    hooks.before(self, &self.execution(Some(self.in_flight.address)));
+                }
+                self.tick_data(bus, &operation, kind, bytes, cycles)?
+            }
+            OperationKind::Implied => self.tick_implied(bus, &operation)?,
+            OperationKind::Branch => self.tick_branch(bus, &operation)?,
+            OperationKind::Jump => self.tick_jump(bus, &addressing_mode)?,
+            OperationKind::Push => self.tick_push(bus, &operation)?,
+            OperationKind::Pull => self.tick_pull(bus, &operation)?,
+            OperationKind::JumpToSubRoutine => self.tick_jsr(bus)?,
+            OperationKind::ReturnFromSubroutine => self.tick_rts(bus)?,
+            OperationKind::ReturnFromInterrupt => self.tick_rti(bus)?,
+            OperationKind::Break => self.tick_break(bus, None)?,
+            OperationKind::Halt => self.tick_halt(bus, &operation)?,
+            OperationKind::BitBranch => self.tick_bit_branch(bus, &operation)?,
+        };
+
+        let Done(result) = step else {
+            return Ok(false);
+        };
+
+        let effective_address = match (operation.kind(), &result) {
+            // a jump has its target only once it is done
+            (OperationKind::Jump, Thingimagic::Jump(target)) => {
+                hooks.before(self, &self.execution(Some(*target)));
+                Some(*target)
+            }
+            (kind, _) if has_effective_address(kind, addressing_mode) => {
+                Some(self.in_flight.address)
+            }
+            _ => None,
+        };
+        let execution = self.execution(effective_address);
+        let cycles = self.in_flight.cycle;
+
+        self.finish(result, bytes, previous);
+        hooks.after(self, &execution, cycles);
+        Ok(true)
+    }
+
+    /// What the hooks get to see of the instruction in flight
+    fn execution(&self, effective_address: Option<u16>) -> Execution {
+        Execution {
+            program_counter: self.program_counter,
+            opcode: self.in_flight.opcode,
+            instruction: V::decode(self.in_flight.opcode),
+            effective_address,
+        }
+    }
+
+    /// Moves on to the next instruction,
+    /// `polled` is what polling the interrupts saw on the cycle before
+    fn finish(&mut self, result: Thingimagic, bytes: u8, polled: bool) -> bool {
+        let operation = V::OPCODES[self.in_flight.opcode as usize].operation;
+
+        if let Thingimagic::Jump(target) = result {
+            match self.in_flight.interrupt {
+                Some(Interrupt::Request) if self.in_flight.hijacked => {
+                    self.track_interrupt(Interrupt::NonMaskable, target)
+                }
+                Some(interrupt) => self.track_interrupt(interrupt, target),
+                None => self.track_instruction(operation.kind(), target),
+            }
+        }
+
+        let due = match (self.in_flight.interrupt, operation.kind(), &result) {
+            // neither interrupt sequences nor BRK poll, the handler gets to run its first instruction
+            (Some(_), ..) | (None, OperationKind::Break, _) => false,
+            // a taken branch polls on its operand and again only if it has to fix up the page
+            (None, OperationKind::Branch, Thingimagic::Branch(target)) => {
+                let next = self.program_counter.wrapping_add(bytes as u16);
+                self.in_flight.fetch_polled || (polled && page_crossed(next, *target))
+            }
+            _ => polled,
+        };
+        self.set_interrupt_due(due);
+
+        self.program_counter = match result {
+            Thingimagic::Jump(ptr) | Thingimagic::Branch(ptr) => ptr,
+            Thingimagic::Increment => self.program_counter.wrapping_add(bytes as u16),
+        };
+        self.in_flight = InFlight::default();
+        true
+    }
+
+    /// Resolves the effective address, returns false once there is nothing left to resolve
+    /// and the operation can use the current cycle.
+    ///
+    /// Operations with a page penalty skip the cycle that fixes up the high byte
+    /// when indexing didn't cross a page
+    fn tick_address(
+        &mut self,
+        bus: &mut impl OpenBus,
+        addressing_mode: &AdressingMode,
+        skips_fixup: bool,
+    ) -> Result<bool, Fault> {
+        use AdressingMode::*;
+
+        let operand = self.program_counter.wrapping_add(1);
+        // the 65C02 rereads the last operand byte instead of touching a wrong address
+        let cmos = V::CMOS;
+        let state = &mut self.in_flight;
+
+        match (addressing_mode, state.cycle) {
+            (Immediate, 2) => {
+                state.address = operand;
+                return Ok(false);
+            }
+
+            (ZeroPage, 2) => state.address = bus.read(operand)? as u16,
+
+            (IndexedZeroPageX | IndexedZeroPageY, 2) => state.pointer = bus.read(operand)?,
+            (IndexedZeroPageX | IndexedZeroPageY, 3) => {
+                bus.read(if cmos { operand } else { state.pointer as u16 })?;
+                let index = match addressing_mode {
+                    IndexedZeroPageX => self.register_x,
+                    _ => self.register_y,
+                };
+                state.address = state.pointer.wrapping_add(index) as u16;
+            }
+
+            (Absolute | IndexedAbsoluteX | IndexedAbsoluteY, 2) => {
+                state.address = bus.read(operand)? as u16
+            }
+            (Absolute, 3) => {
+                state.address |= (bus.read(operand.wrapping_add(1))? as u16) << 8;
+            }
+            (IndexedAbsoluteX | IndexedAbsoluteY, 3) => {
+                let high_byte = bus.read(operand.wrapping_add(1))?;
+                let index = match addressing_mode {
+                    IndexedAbsoluteX => self.register_x,
+                    _ => self.register_y,
+                };
+                let (low_byte, carry) = (state.address as u8).overflowing_add(index);
+                state.address = u16::from_le_bytes([low_byte, high_byte]);
+                state.page_crossed = carry;
+            }
+
+            (IndexedIndirect, 2) => state.pointer = bus.read(operand)?,
+            (IndexedIndirect, 3) => {
+                bus.read(if cmos { operand } else { state.pointer as u16 })?;
+                state.pointer = state.pointer.wrapping_add(self.register_x);
+            }
+            (IndexedIndirect, 4) => state.address = bus.read(state.pointer as u16)? as u16,
+            (IndexedIndirect, 5) => {
+                let high_byte = bus.read(state.pointer.wrapping_add(1) as u16)?;
+                state.address |= (high_byte as u16) << 8;
+            }
+
+            (IndirectIndexed, 2) => state.pointer = bus.read(operand)?,
+            (IndirectIndexed, 3) => state.address = bus.read(state.pointer as u16)? as u16,
+            (IndirectIndexed, 4) => {
+                let high_byte = bus.read(state.pointer.wrapping_add(1) as u16)?;
+                let (low_byte, carry) = (state.address as u8).overflowing_add(self.register_y);
+                state.address = u16::from_le_bytes([low_byte, high_byte]);
+                state.page_crossed = carry;
+            }
+
+            (ZeroPageIndirect, 2) => state.pointer = bus.read(operand)?,
+            (ZeroPageIndirect, 3) => state.address = bus.read(state.pointer as u16)? as u16,
+            (ZeroPageIndirect, 4) => {
+                let high_byte = bus.read(state.pointer.wrapping_add(1) as u16)?;
+                state.address |= (high_byte as u16) << 8;
+            }
+
+            // the address is read before the carry made it into the high byte,
+            // reads that didn't cross a page can use it right away
+            (IndexedAbsoluteX | IndexedAbsoluteY, 4) | (IndirectIndexed, 5) => {
+                if skips_fixup && !state.page_crossed {
+                    return Ok(false);
+                }
+                let last_operand = match addressing_mode {
+                    IndirectIndexed => operand,
+                    _ => operand.wrapping_add(1),
+                };
+                bus.read(if cmos { last_operand } else { state.address })?;
+                if state.page_crossed {
+                    state.address = state.address.wrapping_add(0x0100);
+                }
+            }
+
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Transfers the data of read, write and read-modify-write operations
+    fn tick_data(
+        &mut self,
+        bus: &mut impl OpenBus,
+        operation: &Operation,
+        kind: OperationKind,
+        bytes: u8,
+        cycles: u8,
+    ) -> Result<Step, Fault> {
+        let address = self.in_flight.address;
+        // the long NOPs of the 65C02 keep reading until they used up their cycles
+        let padded = matches!(operation, Operation::NoOp) && self.in_flight.cycle < cycles;
+
+        match (kind, self.in_flight.cycle - self.in_flight.data_start) {
+            (OperationKind::Read, 0) => {
+                let mut latch = Latch::new(address, bus.read(address)?);
+                let result = operation.run(self, &mut latch, Pointer(address))?;
+                match operation.has_decimal_penalty(self) || padded {
+                    true => Ok(Pending),
+                    false => Ok(Done(result)),
+                }
+            }
+            (OperationKind::Read, _) if operation.has_decimal_penalty(self) => {
+                bus.read(self.program_counter.wrapping_add(bytes as u16))?;
+                Ok(Done(Thingimagic::Increment))
+            }
+            (OperationKind::Read, _) => {
+                bus.read(address)?;
+                match padded {
+                    true => Ok(Pending),
+                    false => Ok(Done(Thingimagic::Increment)),
+                }
+            }
+            (OperationKind::Write, 0) => {
+                let mut latch = Latch::new(address, 0);
+                let result = operation.run(self, &mut latch, Pointer(address))?;
+                bus.write(latch.addr, latch.value)?;
+                Ok(Done(result))
+            }
+            (OperationKind::ReadModifyWrite, 0) => {
+                self.in_flight.data = bus.read(address)?;
+                Ok(Pending)
+            }
+            (OperationKind::ReadModifyWrite, 1) => {
+                // the unmodified value gets written back while the operation runs,
+                // the 65C02 reads it again instead
+                let value = self.in_flight.data;
+                match V::CMOS {
+                    true => bus.read(address).map(|_| ())?,
+                    false => bus.write(address, value)?,
+                }
+                let mut latch = Latch::new(address, value);
+                operation.run(self, &mut latch, Pointer(address))?;
+                self.in_flight.data = latch.value;
+                Ok(Pending)
+            }
+            (OperationKind::ReadModifyWrite, 2) => {
+                bus.write(address, self.in_flight.data)?;
+                Ok(Done(Thingimagic::Increment))
+            }
+            _ => Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+    }
+
+    /// Operations that only work on registers, the cycle after the opcode reads the next byte
+    fn tick_implied(
+        &mut self,
+        bus: &mut impl OpenBus,
+        operation: &Operation,
+    ) -> Result<Step, Fault> {
+        let next = self.program_counter.wrapping_add(1);
+        let mut latch = Latch::new(next, bus.read(next)?);
+        operation
+            .run(self, &mut latch, Value(self.accumulator))
+            .map(Done)
+    }
+
+    fn tick_branch(
+        &mut self,
+        bus: &mut impl OpenBus,
+        operation: &Operation,
+    ) -> Result<Step, Fault> {
+        let next = self.program_counter.wrapping_add(2);
+
+        match self.in_flight.cycle {
+            2 => {
+                let offset = bus.read(self.program_counter.wrapping_add(1))?;
+                let mut latch = Latch::new(0, 0);
+                match operation.run(self, &mut latch, Value(offset))? {
+                    Thingimagic::Branch(target) => {
+                        self.in_flight.address = target;
+                        Ok(Pending)
+                    }
+                    result => Ok(Done(result)),
+                }
+            }
+            3 | 4 => self.tick_taken_branch(bus, next, self.in_flight.cycle - 3),
+            _ => Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+    }
+
+    /// BBR and BBS read the zero page byte twice before they get to the offset
+    fn tick_bit_branch(
+        &mut self,
+        bus: &mut impl OpenBus,
+        operation: &Operation,
+    ) -> Result<Step, Fault> {
+        let next = self.program_counter.wrapping_add(3);
+
+        match self.in_flight.cycle {
+            2 => {
+                self.in_flight.address = bus.read(self.program_counter.wrapping_add(1))? as u16;
+                Ok(Pending)
+            }
+            3 => {
+                self.in_flight.data = bus.read(self.in_flight.address)?;
+                Ok(Pending)
+            }
+            4 => {
+                bus.read(self.in_flight.address)?;
+                Ok(Pending)
+            }
+            5 => {
+                let offset_addr = self.program_counter.wrapping_add(2);
+                let mut latch = Latch::new(offset_addr, bus.read(offset_addr)?);
+                match operation.run(self, &mut latch, Value(self.in_flight.data))? {
+                    Thingimagic::Branch(target) => {
+                        self.in_flight.address = target;
+                        Ok(Pending)
+                    }
+                    result => Ok(Done(result)),
+                }
+            }
+            6 | 7 => self.tick_taken_branch(bus, next, self.in_flight.cycle - 6),
+            _ => Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+    }
+
+    /// Cycles a taken branch spends on moving to its target,
+    /// the second one is only needed when the target is on another page
+    fn tick_taken_branch(
+        &mut self,
+        bus: &mut impl OpenBus,
+        next: u16,
+        step: u8,
+    ) -> Result<Step, Fault> {
+        let target = self.in_flight.address;
+
+        match step {
+            0 => {
+                bus.read(next)?;
+                match page_crossed(next, target) {
+                    true => Ok(Pending),
+                    false => Ok(Done(Thingimagic::Branch(target))),
+                }
+            }
+            1 => {
+                // the low byte is already updated, the high byte isn't
+                bus.read(fixed_up(next, target))?;
+                Ok(Done(Thingimagic::Branch(target)))
+            }
+            _ => Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+    }
+
+    fn tick_jump(
+        &mut self,
+        bus: &mut impl OpenBus,
+        addressing_mode: &AdressingMode,
+    ) -> Result<Step, Fault> {
+        use AdressingMode::*;
+
+        let operand = self.program_counter.wrapping_add(1);
+        let cmos = V::CMOS;
+        let register_x = self.register_x;
+        let state = &mut self.in_flight;
+
+        match (addressing_mode, state.cycle) {
+            (_, 2) => state.address = bus.read(operand)? as u16,
+            (Absolute, 3) => {
+                let high_byte = bus.read(operand.wrapping_add(1))? as u16;
+                return Ok(Done(Thingimagic::Jump(state.address | high_byte << 8)));
+            }
+            (AbsoluteIndirect | AbsoluteIndexedIndirect, 3) => {
+                state.address |= (bus.read(operand.wrapping_add(1))? as u16) << 8;
+            }
+            // the 65C02 spends a cycle on adding the index or on getting the carry right
+            (AbsoluteIndexedIndirect, 4) => {
+                bus.read(operand.wrapping_add(1))?;
+                state.address = state.address.wrapping_add(register_x as u16);
+            }
+            (AbsoluteIndirect, 4) if cmos => {
+                bus.read(operand.wrapping_add(1))?;
+            }
+            (AbsoluteIndirect, 4) | (AbsoluteIndexedIndirect, 5) => {
+                state.data = bus.read(state.address)?
+            }
+            (AbsoluteIndirect, 5) if cmos => state.data = bus.read(state.address)?,
+            (AbsoluteIndirect | AbsoluteIndexedIndirect, 6) => {
+                let high_byte = bus.read(state.address.wrapping_add(1))?;
+                let target = u16::from_le_bytes([state.data, high_byte]);
+                return Ok(Done(Thingimagic::Jump(target)));
+            }
+            (AbsoluteIndirect, 5) => {
+                // due to a hardware bug the addition doesnt carry into the high byte
+                let [low_byte, high_byte] = state.address.to_le_bytes();
+                let high_addr = u16::from_le_bytes([low_byte.wrapping_add(1), high_byte]);
+                let target = u16::from_le_bytes([state.data, bus.read(high_addr)?]);
+                return Ok(Done(Thingimagic::Jump(target)));
+            }
+            _ => return Err(Fault::UnexpectedCycle(state.cycle)),
+        }
+
+        Ok(Pending)
+    }
+
+    fn tick_push(&mut self, bus: &mut impl OpenBus, operation: &Operation) -> Result<Step, Fault> {
+        match self.in_flight.cycle {
+            2 => {
+                bus.read(self.program_counter.wrapping_add(1))?;
+                Ok(Pending)
+            }
+            3 => {
+                let mut latch = Latch::new(0, 0);
+                let result = operation.run(self, &mut latch, Value(self.accumulator))?;
+                bus.write(latch.addr, latch.value)?;
+                Ok(Done(result))
+            }
+            _ => Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+    }
+
+    fn tick_pull(&mut self, bus: &mut impl OpenBus, operation: &Operation) -> Result<Step, Fault> {
+        match self.in_flight.cycle {
+            2 => {
+                bus.read(self.program_counter.wrapping_add(1))?;
+                Ok(Pending)
+            }
+            3 => {
+                bus.read(self.stack_address())?;
+                Ok(Pending)
+            }
+            4 => {
+                let addr = STACK_PAGE.wrapping_add(self.stack_pointer.wrapping_add(1) as u16);
+                let mut latch = Latch::new(addr, bus.read(addr)?);
+                operation
+                    .run(self, &mut latch, Value(self.accumulator))
+                    .map(Done)
+            }
+            _ => Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+    }
+
+    fn tick_jsr(&mut self, bus: &mut impl OpenBus) -> Result<Step, Fault> {
+        let [return_low, return_high] = self.program_counter.wrapping_add(2).to_le_bytes();
+
+        match self.in_flight.cycle {
+            2 => self.in_flight.address = bus.read(self.program_counter.wrapping_add(1))? as u16,
+            3 => {
+                bus.read(self.stack_address())?;
+            }
+            4 => self.push(bus, return_high)?,
+            5 => self.push(bus, return_low)?,
+            6 => {
+                let high_byte = bus.read(self.program_counter.wrapping_add(2))? as u16;
+                let target = self.in_flight.address | high_byte << 8;
+                return Ok(Done(Thingimagic::Jump(target)));
+            }
+            _ => return Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+
+        Ok(Pending)
+    }
+
+    fn tick_rts(&mut self, bus: &mut impl OpenBus) -> Result<Step, Fault> {
+        match self.in_flight.cycle {
+            2 => {
+                bus.read(self.program_counter.wrapping_add(1))?;
+            }
+            3 => {
+                bus.read(self.stack_address())?;
+            }
+            4 => self.in_flight.address = self.pull(bus)? as u16,
+            5 => self.in_flight.address |= (self.pull(bus)? as u16) << 8,
+            6 => {
+                bus.read(self.in_flight.address)?;
+                return Ok(Done(Thingimagic::Jump(
+                    self.in_flight.address.wrapping_add(1),
+                )));
+            }
+            _ => return Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+
+        Ok(Pending)
+    }
+
+    fn tick_rti(&mut self, bus: &mut impl OpenBus) -> Result<Step, Fault> {
+        match self.in_flight.cycle {
+            2 => {
+                bus.read(self.program_counter.wrapping_add(1))?;
+            }
+            3 => {
+                bus.read(self.stack_address())?;
+            }
+            4 => {
+                let addr = STACK_PAGE.wrapping_add(self.stack_pointer.wrapping_add(1) as u16);
+                let mut latch = Latch::new(addr, bus.read(addr)?);
+                Operation::PullStatusFromStack.run(self, &mut latch, Value(self.accumulator))?;
+            }
+            5 => self.in_flight.address = self.pull(bus)? as u16,
+            6 => {
+                let target = self.in_flight.address | (self.pull(bus)? as u16) << 8;
+                return Ok(Done(Thingimagic::Jump(target)));
+            }
+            _ => return Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+
+        Ok(Pending)
+    }
+
+    /// BRK and the hardware interrupts share the same sequence.
+    ///
+    /// BRK skips its padding byte and pushes the status with `Break` set,
+    /// a reset reads the stack instead of writing it
+    fn tick_break(
+        &mut self,
+        bus: &mut impl OpenBus,
+        interrupt: Option<Interrupt>,
+    ) -> Result<Step, Fault> {
+        let return_address = match interrupt {
+            None => self.program_counter.wrapping_add(2),
+            Some(_) => self.program_counter,
+        };
+        let [return_low, return_high] = return_address.to_le_bytes();
+
+        let status = match interrupt {
+            None => self.status.to_pushed(true),
+            Some(_) => self.status.to_pushed(false),
+        };
+
+        let vector = match (interrupt, self.in_flight.hijacked) {
+            (Some(Interrupt::Reset), _) => RESET_VECTOR,
+            (_, true) => NMI_VECTOR,
+            _ => IRQ_VECTOR,
+        };
+
+        match (self.in_flight.cycle, interrupt) {
+            (2, None) => {
+                bus.read(self.program_counter.wrapping_add(1))?;
+            }
+            (2, Some(_)) => {
+                bus.read(self.program_counter)?;
+            }
+            (3..=5, Some(Interrupt::Reset)) => {
+                bus.read(self.stack_address())?;
+                self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+            }
+            (3, _) => self.push(bus, return_high)?,
+            (4, _) => {
+                self.push(bus, return_low)?;
+                // an NMI that came in by now takes over the vector
+                self.in_flight.hijacked = self.take_nmi();
+            }
+            (5, _) => self.push(bus, status)?,
+            (6, _) => {
+                self.in_flight.address = bus.read(vector)? as u16;
+                self.status.insert(Flags::InterruptDisable);
+                if V::CMOS {
+                    self.status.remove(Flags::DecimalMode);
+                }
+            }
+            (7, _) => {
+                let target =
+                    self.in_flight.address | (bus.read(vector.wrapping_add(1))? as u16) << 8;
+                return Ok(Done(Thingimagic::Jump(target)));
+            }
+            _ => return Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+
+        Ok(Pending)
+    }
+
+    /// WAI and STP idle for two cycles before they stop the clock
+    fn tick_halt(&mut self, bus: &mut impl OpenBus, operation: &Operation) -> Result<Step, Fault> {
+        let next = self.program_counter.wrapping_add(1);
+        match self.in_flight.cycle {
+            2 => {
+                bus.read(next)?;
+                Ok(Pending)
+            }
+            3 => {
+                let mut latch = Latch::new(next, bus.read(next)?);
+                operation
+                    .run(self, &mut latch, Value(self.accumulator))
+                    .map(Done)
+            }
+            _ => Err(Fault::UnexpectedCycle(self.in_flight.cycle)),
+        }
+    }
+
+    /// Works out what the next tick accesses from the cycle the instruction is on,
+    /// following the same tables as the ticks themselves
+    pub(super) fn next_access(&self) -> NextAccess {
+        use NextAccess::*;
+
+        if self.reset_pending() {
+            return Read(self.program_counter);
+        }
+        if self.jammed || (self.waiting && !self.interrupt_asserted()) {
+            return Idle;
+        }
+
+        let state = &self.in_flight;
+        if state.cycle == 0 {
+            return Read(self.program_counter);
+        }
+
+        let cycle = state.cycle + 1;
+        let program_counter = self.program_counter;
+        let operand = program_counter.wrapping_add(1);
+        let pulled = STACK_PAGE.wrapping_add(self.stack_pointer.wrapping_add(1) as u16);
+
+        if let Some(interrupt) = state.interrupt {
+            return self.next_break_access(Some(interrupt), cycle);
+        }
+
+        let Instruction {
+            operation,
+            addressing_mode,
+            bytes,
+            ..
+        } = V::decode(state.opcode);
+
+        match operation.kind() {
+            OperationKind::Read | OperationKind::ReadModifyWrite
+                if matches!(
+                    addressing_mode,
+                    AdressingMode::Implied | AdressingMode::Accumulator
+                ) =>
+            {
+                Read(operand)
+            }
+            kind
+            @ (OperationKind::Read | OperationKind::Write | OperationKind::ReadModifyWrite) => {
+                let (address, step) = match state.data_start {
+                    0 => match self.next_address_access(&addressing_mode, &operation, cycle) {
+                        Ok(access) => return access,
+                        Err(address) => (address, 0),
+                    },
+                    start => (state.address, cycle - start),
+                };
+
+                match (kind, step) {
+                    (OperationKind::Read, 0) => Read(address),
+                    (OperationKind::Read, _) if operation.has_decimal_penalty(self) => {
+                        Read(program_counter.wrapping_add(bytes as u16))
+                    }
+                    (OperationKind::Read, _) => Read(address),
+                    (OperationKind::ReadModifyWrite, 0) => Read(address),
+                    (OperationKind::ReadModifyWrite, 1) if V::CMOS => Read(address),
+                    _ => Write,
+                }
+            }
+            OperationKind::Implied | OperationKind::Halt => Read(operand),
+            OperationKind::Branch => match cycle {
+                2 => Read(operand),
+                3 => Read(program_counter.wrapping_add(2)),
+                _ => Read(fixed_up(program_counter.wrapping_add(2), state.address)),
+            },
+            OperationKind::BitBranch => match cycle {
+                2 => Read(operand),
+                3 | 4 => Read(state.address),
+                5 => Read(program_counter.wrapping_add(2)),
+                6 => Read(program_counter.wrapping_add(3)),
+                _ => Read(fixed_up(program_counter.wrapping_add(3), state.address)),
+            },
+            OperationKind::Jump => self.next_jump_access(&addressing_mode, cycle),
+            OperationKind::Push => match cycle {
+                2 => Read(operand),
+                _ => Write,
+            },
+            OperationKind::Pull => match cycle {
+                2 => Read(operand),
+                3 => Read(self.stack_address()),
+                _ => Read(pulled),
+            },
+            OperationKind::JumpToSubRoutine => match cycle {
+                2 => Read(operand),
+                3 => Read(self.stack_address()),
+                4 | 5 => Write,
+                _ => Read(program_counter.wrapping_add(2)),
+            },
+            OperationKind::ReturnFromSubroutine => match cycle {
+                2 => Read(operand),
+                3 => Read(self.stack_address()),
+                4 | 5 => Read(pulled),
+                _ => Read(state.address),
+            },
+            OperationKind::ReturnFromInterrupt => match cycle {
+                2 => Read(operand),
+                3 => Read(self.stack_address()),
+                _ => Read(pulled),
+            },
+            OperationKind::Break => self.next_break_access(None, cycle),
+        }
+    }
+
+    /// The access [`IC6502::tick_address`] makes on the cycle,
+    /// or the effective address if it is resolved and the operation can use the cycle
+    fn next_address_access(
+        &self,
+        addressing_mode: &AdressingMode,
+        operation: &Operation,
+        cycle: u8,
+    ) -> Result<NextAccess, u16> {
+        use AdressingMode::*;
+        use NextAccess::*;
+
+        let operand = self.program_counter.wrapping_add(1);
+        let cmos = V::CMOS;
+        let state = &self.in_flight;
+
+        let access = match (addressing_mode, cycle) {
+            (Immediate, 2) => return Err(operand),
+
+            (ZeroPage | IndexedZeroPageX | IndexedZeroPageY, 2)
+            | (Absolute | IndexedAbsoluteX | IndexedAbsoluteY, 2)
+            | (IndexedIndirect | IndirectIndexed | ZeroPageIndirect, 2) => Read(operand),
+
+            (IndexedZeroPageX | IndexedZeroPageY | IndexedIndirect, 3) => {
+                Read(if cmos { operand } else { state.pointer as u16 })
+            }
+            (Absolute | IndexedAbsoluteX | IndexedAbsoluteY, 3) => Read(operand.wrapping_add(1)),
+
+            (IndexedIndirect, 4) | (IndirectIndexed | ZeroPageIndirect, 3) => {
+                Read(state.pointer as u16)
+            }
+            (IndexedIndirect, 5) | (IndirectIndexed | ZeroPageIndirect, 4) => {
+                Read(state.pointer.wrapping_add(1) as u16)
+            }
+
+            (IndexedAbsoluteX | IndexedAbsoluteY, 4) | (IndirectIndexed, 5) => {
+                if operation.has_page_penalty::<V>() && !state.page_crossed {
+                    return Err(state.address);
+                }
+                let last_operand = match addressing_mode {
+                    IndirectIndexed => operand,
+                    _ => operand.wrapping_add(1),
+                };
+                Read(if cmos { last_operand } else { state.address })
+            }
+
+            _ => return Err(state.address),
+        };
+        Ok(access)
+    }
+
+    /// The access [`IC6502::tick_jump`] makes on the cycle
+    fn next_jump_access(&self, addressing_mode: &AdressingMode, cycle: u8) -> NextAccess {
+        use AdressingMode::*;
+        use NextAccess::*;
+
+        let operand = self.program_counter.wrapping_add(1);
+        let address = self.in_flight.address;
+
+        match (addressing_mode, cycle) {
+            (_, 2) => Read(operand),
+            (_, 3) | (AbsoluteIndexedIndirect, 4) => Read(operand.wrapping_add(1)),
+            (AbsoluteIndirect, 4) if V::CMOS => Read(operand.wrapping_add(1)),
+            (AbsoluteIndirect, 4) | (AbsoluteIndexedIndirect, 5) => Read(address),
+            (AbsoluteIndirect, 5) if V::CMOS => Read(address),
+            (AbsoluteIndirect, 5) => {
+                let [low_byte, high_byte] = address.to_le_bytes();
+                Read(u16::from_le_bytes([low_byte.wrapping_add(1), high_byte]))
+            }
+            _ => Read(address.wrapping_add(1)),
+        }
+    }
+
+    /// The access [`IC6502::tick_break`] makes on the cycle
+    fn next_break_access(&self, interrupt: Option<Interrupt>, cycle: u8) -> NextAccess {
+        use NextAccess::*;
+
+        let vector = match (interrupt, self.in_flight.hijacked) {
+            (Some(Interrupt::Reset), _) => RESET_VECTOR,
+            (_, true) => NMI_VECTOR,
+            _ => IRQ_VECTOR,
+        };
+
+        match (cycle, interrupt) {
+            (2, None) => Read(self.program_counter.wrapping_add(1)),
+            (2, Some(_)) => Read(self.program_counter),
+            (3..=5, Some(Interrupt::Reset)) => Read(self.stack_address()),
+            (3..=5, _) => Write,
+            (6, _) => Read(vector),
+            _ => Read(vector.wrapping_add(1)),
+        }
+    }
+
+    fn stack_address(&self) -> u16 {
+        STACK_PAGE.wrapping_add(self.stack_pointer as u16)
+    }
+
+    fn push(&mut self, bus: &mut impl OpenBus, value: u8) -> Result<(), BusError> {
+        bus.write(self.stack_address(), value)?;
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        Ok(())
+    }
+
+    fn pull(&mut self, bus: &mut impl OpenBus) -> Result<u8, BusError> {
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        bus.read(self.stack_address())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::ic6502::{IC6502, Variant};
+
+/// How the unstable unofficial opcodes of the NMOS chips behave.
+///
+/// XAA and LXA OR the accumulator with a constant that depends on the chip and even
+/// its temperature, the SHx opcodes (SHA, SHX, SHY and TAS) AND the stored value with
+/// the high byte of the address and get the address wrong when indexing crosses a page.
+/// The presets match what tests and games expect, the CMOS chips run NOPs instead
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UnstableOpcodes {
+    /// Constant XAA ORs into the accumulator before ANDing it with X and the operand
+    pub xaa_magic: u8,
+    /// Constant LXA ORs into the accumulator before ANDing it with the operand
+    pub lxa_magic: u8,
+    /// Whether the SHx opcodes AND the stored value with the high byte of the base address plus one
+    pub sh_and_high: bool,
+    /// Whether the stored value of the SHx opcodes replaces the high byte of the address
+    /// when indexing crossed a page
+    pub sh_page_cross_corrupts: bool,
+}
+
+impl UnstableOpcodes {
+    /// What the SingleStepTests were recorded with
+    pub const SINGLE_STEP_TESTS: Self = Self {
+        xaa_magic: 0xEE,
+        lxa_magic: 0xEE,
+        sh_and_high: true,
+        sh_page_cross_corrupts: true,
+    };
+
+    /// What the 2A03 in most NES consoles does, XAA and LXA behave as if the constant was `$FF`
+    pub const NES: Self = Self {
+        xaa_magic: 0xFF,
+        lxa_magic: 0xFF,
+        ..Self::SINGLE_STEP_TESTS
+    };
+}
+
+/// The NES preset, like the default variant
+impl Default for UnstableOpcodes {
+    fn default() -> Self {
+        Self::NES
+    }
+}
+
+impl<V: Variant> IC6502<V> {
+    pub fn unstable_opcodes(&self) -> &UnstableOpcodes {
+        &self.unstable
+    }
+
+    pub fn set_unstable_opcodes(&mut self, unstable: UnstableOpcodes) {
+        self.unstable = unstable;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bus::{FlatMemory, OpenBusDevice},
+        ic6502::Ricoh2A03,
+    };
+
+    /// Runs a single instruction at $0200 by cycling and by ticking,
+    /// both have to end up in the same state
+    fn run(unstable: UnstableOpcodes, code: &[u8], a: u8, x: u8, y: u8) -> (IC6502, FlatMemory) {
+        let setup = || {
+            let cpu: IC6502 = IC6502::builder()
+                .program_counter(0x0200)
+                .accumulator(a)
+                .register_x(x)
+                .register_y(y)
+                .unstable_opcodes(unstable)
+                .build();
+            (cpu, FlatMemory::with_binary(0x0200, code))
+        };
+
+        let (mut cycled, mut cycled_memory) = setup();
+        cycled.cycle(&mut cycled_memory).unwrap();
+        let (mut ticked, mut ticked_memory) = setup();
+        while !ticked.tick(&mut ticked_memory).unwrap() {}
+
+        assert_eq!(cycled, ticked);
+        assert_eq!(cycled_memory.as_slice(), ticked_memory.as_slice());
+        (cycled, cycled_memory)
+    }
+
+    #[test]
+    fn nes_by_default() {
+        assert_eq!(UnstableOpcodes::default(), UnstableOpcodes::NES);
+        assert_eq!(
+            *IC6502::<Ricoh2A03>::default().unstable_opcodes(),
+            UnstableOpcodes::NES
+        );
+    }
+
+    #[test]
+    fn xaa_and_lxa_magic() {
+        for (unstable, magic) in [
+            (UnstableOpcodes::NES, 0xFF),
+            (UnstableOpcodes::SINGLE_STEP_TESTS, 0xEE),
+        ] {
+            // XAA #$FF
+            let (cpu, _) = run(unstable, &[0x8B, 0xFF], 0x00, 0xFF, 0x00);
+            assert_eq!(cpu.accumulator(), magic);
+
+            // LXA #$FF
+            let (cpu, _) = run(unstable, &[0xAB, 0xFF], 0x00, 0x00, 0x00);
+            assert_eq!(cpu.accumulator(), magic);
+            assert_eq!(cpu.register_x(), magic);
+        }
+    }
+
+    #[test]
+    fn sh_opcodes_crossing_a_page() {
+        let quirkless = UnstableOpcodes {
+            sh_and_high: false,
+            sh_page_cross_corrupts: false,
+            ..UnstableOpcodes::NES
+        };
+
+        // SHA, SHX and TAS $02F0,Y and SHY $02F0,X all store 5, indexed by $20
+        for (opcode, x, y) in [
+            (0x9F, 0x05, 0x20),
+            (0x9E, 0x05, 0x20),
+            (0x9B, 0x05, 0x20),
+            (0x9C, 0x20, 0x05),
+        ] {
+            for unstable in [UnstableOpcodes::NES, UnstableOpcodes::SINGLE_STEP_TESTS] {
+                // 5 AND $03 replaces the high byte of $0310
+                let (_, memory) = run(unstable, &[opcode, 0xF0, 0x02], 0x05, x, y);
+                assert_eq!(memory.as_slice()[0x0110], 0x01, "{opcode:02X}");
+                assert_eq!(memory.as_slice()[0x0310], 0x00, "{opcode:02X}");
+            }
+
+            let (_, memory) = run(quirkless, &[opcode, 0xF0, 0x02], 0x05, x, y);
+            assert_eq!(memory.as_slice()[0x0110], 0x00, "{opcode:02X}");
+            assert_eq!(memory.as_slice()[0x0310], 0x05, "{opcode:02X}");
+        }
+    }
+}
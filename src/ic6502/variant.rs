@@ -0,0 +1,149 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::{
+    bus::{BusError, OpenBus},
+    ic6502::opcodes::{self, Instruction, OpcodeInfo, opcode_table},
+};
+
+/// Member of the 6502 family the CPU behaves like, picked through the type parameter of
+/// [`IC6502`](crate::ic6502::IC6502).
+///
+/// Everything that differs between the chips is decided at compile time,
+/// so a CPU pays nothing for the variants it doesn't emulate
+pub trait Variant: Debug + Copy + Default + Eq + Send + Sync + 'static {
+    /// Whether the `DecimalMode` flag has an effect on ADC and SBC
+    const DECIMAL_MODE: bool;
+    /// Whether `JMP ($xxFF)` fetches the high byte of the target from `$xx00`
+    const INDIRECT_JUMP_BUG: bool;
+    /// Whether the chip is one of the CMOS 65C02s.
+    ///
+    /// They clear the decimal flag on interrupts, set N and Z after decimal arithmetic
+    /// and do their dummy accesses differently
+    const CMOS: bool;
+    /// Address lines the chip has, every address is masked with this before it reaches the bus
+    const ADDRESS_MASK: u16;
+    /// Whether the chip has IRQ and NMI pins
+    const INTERRUPT_LINES: bool;
+
+    /// What the chip does for each opcode, indexed by the opcode
+    const OPCODES: &'static [OpcodeInfo; 256];
+
+    /// Decodes the opcode the way the chip does
+    fn decode(opcode: u8) -> Instruction {
+        Self::OPCODES[opcode as usize].instruction()
+    }
+}
+
+/// The NES CPU, a NMOS 6502 with the decimal mode cut off
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Ricoh2A03;
+
+impl Variant for Ricoh2A03 {
+    const DECIMAL_MODE: bool = false;
+    const INDIRECT_JUMP_BUG: bool = true;
+    const CMOS: bool = false;
+    const ADDRESS_MASK: u16 = 0xFFFF;
+    const INTERRUPT_LINES: bool = true;
+    const OPCODES: &'static [OpcodeInfo; 256] = &opcode_table!(Self, opcodes::nmos6502);
+}
+
+/// A stock NMOS 6502, `SED` switches ADC and SBC to decimal arithmetic
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Nmos6502;
+
+impl Variant for Nmos6502 {
+    const DECIMAL_MODE: bool = true;
+    const INDIRECT_JUMP_BUG: bool = true;
+    const CMOS: bool = false;
+    const ADDRESS_MASK: u16 = 0xFFFF;
+    const INTERRUPT_LINES: bool = true;
+    const OPCODES: &'static [OpcodeInfo; 256] = &opcode_table!(Self, opcodes::nmos6502);
+}
+
+/// The 6502 in a smaller package, used by the Atari 2600.
+///
+/// Only 13 address lines are bonded out, so the bus sees every address mirrored
+/// eight times over the address space. IRQ and NMI aren't bonded out at all
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Mos6507;
+
+impl Variant for Mos6507 {
+    const DECIMAL_MODE: bool = true;
+    const INDIRECT_JUMP_BUG: bool = true;
+    const CMOS: bool = false;
+    const ADDRESS_MASK: u16 = 0x1FFF;
+    const INTERRUPT_LINES: bool = false;
+    const OPCODES: &'static [OpcodeInfo; 256] = &opcode_table!(Self, opcodes::nmos6502);
+}
+
+/// The CMOS redesign by WDC with new opcodes and addressing modes,
+/// the unofficial NMOS opcodes are replaced by NOPs
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Wdc65C02;
+
+impl Variant for Wdc65C02 {
+    const DECIMAL_MODE: bool = true;
+    const INDIRECT_JUMP_BUG: bool = false;
+    const CMOS: bool = true;
+    const ADDRESS_MASK: u16 = 0xFFFF;
+    const INTERRUPT_LINES: bool = true;
+    const OPCODES: &'static [OpcodeInfo; 256] = &opcode_table!(Self, opcodes::wdc65c02);
+}
+
+/// The Rockwell 65C02, has the bit instructions of the WDC chip but no WAI and STP
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Rockwell65C02;
+
+impl Variant for Rockwell65C02 {
+    const DECIMAL_MODE: bool = true;
+    const INDIRECT_JUMP_BUG: bool = false;
+    const CMOS: bool = true;
+    const ADDRESS_MASK: u16 = 0xFFFF;
+    const INTERRUPT_LINES: bool = true;
+    const OPCODES: &'static [OpcodeInfo; 256] = &opcode_table!(Self, opcodes::rockwell65c02);
+}
+
+/// The Synertek 65C02, has neither the bit instructions nor WAI and STP
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Synertek65C02;
+
+impl Variant for Synertek65C02 {
+    const DECIMAL_MODE: bool = true;
+    const INDIRECT_JUMP_BUG: bool = false;
+    const CMOS: bool = true;
+    const ADDRESS_MASK: u16 = 0xFFFF;
+    const INTERRUPT_LINES: bool = true;
+    const OPCODES: &'static [OpcodeInfo; 256] = &opcode_table!(Self, opcodes::synertek65c02);
+}
+
+/// The bus as the chip sees it, with the address lines it doesn't have cut off
+pub(super) struct AddressLines<'a, B: OpenBus, V: Variant> {
+    bus: &'a mut B,
+    variant: PhantomData<V>,
+}
+
+impl<'a, B: OpenBus, V: Variant> AddressLines<'a, B, V> {
+    #[inline(always)]
+    pub(super) fn new(bus: &'a mut B) -> Self {
+        Self {
+            bus,
+            variant: PhantomData,
+        }
+    }
+}
+
+impl<B: OpenBus, V: Variant> OpenBus for AddressLines<'_, B, V> {
+    #[inline(always)]
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
+        self.bus.read(addr & V::ADDRESS_MASK)
+    }
+
+    #[inline(always)]
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
+        self.bus.write(addr & V::ADDRESS_MASK, byte)
+    }
+    #[inline(always)]
+    fn canonical_address(&self, addr: u16) -> u16 {
+        self.bus.canonical_address(addr & V::ADDRESS_MASK)
+    }
+}
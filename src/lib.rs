@@ -0,0 +1,5 @@
+pub mod bus;
+pub mod ic6502;
+pub mod memory_map;
+pub mod scheduler;
+pub mod test;
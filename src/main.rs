@@ -1,77 +1,105 @@
-mod bus;
-mod ic6502;
-mod test;
-
-use bus::*;
-use ic6502::IC6502;
+use radical_shyboy::bus::*;
+use radical_shyboy::ic6502::{
+    CpuError, IC6502, Nmos6502, Operation, Ricoh2A03, Rockwell65C02, Synertek65C02,
+    UnstableOpcodes, Variant, Wdc65C02,
+};
+use radical_shyboy::test::TestCase;
 use rayon::prelude::*;
-use test::TestCase;
-
-use crate::ic6502::{Instruction, Operation};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-fn load_tests_rayon_json() -> Result<impl ParallelIterator<Item = Vec<TestCase<IC6502>>>> {
-    let mut dir: Vec<_> = std::fs::read_dir("./65x02/nes6502/v1")?
-        .filter_map(|f| f.ok())
-        .collect();
-
-    dir.sort_by(|a, b| {
-        let a_name = &a.file_name().into_string().unwrap()[0..2];
-        let b_name = &b.file_name().into_string().unwrap()[0..2];
-        let a_val = u16::from_str_radix(a_name, 16).unwrap();
-        let b_val = u16::from_str_radix(b_name, 16).unwrap();
-        a_val.cmp(&b_val)
-    });
+fn load_tests_rayon_json<V: Variant>(
+    path: &'static str,
+) -> std::io::Result<impl ParallelIterator<Item = Vec<TestCase<IC6502<V>>>>> {
+    let mut dir: Vec<_> = std::fs::read_dir(path)?.filter_map(|f| f.ok()).collect();
+
+    dir.sort_by_key(opcode_of);
 
     let suites = dir
         .into_par_iter()
         .filter(|f| {
-            f.file_name()
-                .into_string()
-                .ok()
-                .and_then(|f| u8::from_str_radix(&f[0..2], 16).ok())
-                .map(|code| match code.into() {
-                    Instruction::Invalid => false,
-                    // Instruction::Valid {
-                    //     operation: Operation::BranchOnCarrySet,
-                    //     ..
-                    // } => true,
-                    Instruction::Valid { .. } => true,
+            opcode_of(f)
+                // the real chip keeps the bus busy after jamming
+                // which can't be compared against a finite list of cycles
+                .map(|code| {
+                    !matches!(
+                        V::OPCODES[code as usize].operation,
+                        Operation::Jam | Operation::Stop
+                    )
                 })
                 .unwrap_or(false)
         })
-        .filter_map(|f| std::fs::read_to_string(f.path()).ok())
-        .filter_map(|json| serde_json::from_str::<Vec<TestCase<IC6502>>>(&json).ok());
+        .filter_map(|f| {
+            let path = f.path();
+            let suite = std::fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|error| error.to_string()));
+            suite
+                .inspect_err(|error| eprintln!("skipping {}: {error}", path.display()))
+                .ok()
+        });
 
     Ok(suites)
 }
 
+/// Opcode a test file is named after, e.g. `a9.json`
+fn opcode_of(file: &std::fs::DirEntry) -> Option<u8> {
+    let name = file.file_name().into_string().ok()?;
+    u8::from_str_radix(name.get(0..2)?, 16).ok()
+}
+
+/// Runs every test suite directory against the chip it was recorded on
 fn main() -> Result<()> {
-    println!("Loading Test Cases...");
+    run_directory::<Ricoh2A03>("./65x02/nes6502/v1")?;
+    run_directory::<Nmos6502>("./65x02/6502/v1")?;
+    run_directory::<Wdc65C02>("./65x02/wdc65c02/v1")?;
+    run_directory::<Rockwell65C02>("./65x02/rockwell65c02/v1")?;
+    run_directory::<Synertek65C02>("./65x02/synertek65c02/v1")?;
+    Ok(())
+}
+
+fn run_directory<V: Variant>(path: &'static str) -> Result<()> {
+    println!(
+        "Loading Test Cases from {path} ({})...",
+        std::any::type_name::<V>()
+            .rsplit("::")
+            .next()
+            .unwrap_or_default()
+    );
 
     let start = std::time::Instant::now();
-    let suites = load_tests_rayon_json()?;
+    let suites = match load_tests_rayon_json::<V>(path) {
+        Ok(suites) => suites,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            println!("no test files found in {path}");
+            return Ok(());
+        }
+        Err(error) => return Err(error.into()),
+    };
 
     let start_load = std::time::Instant::now();
-    let suites = suites.collect::<Vec<Vec<TestCase<IC6502>>>>();
+    let suites = suites.collect::<Vec<Vec<TestCase<IC6502<V>>>>>();
     let end_load = std::time::Instant::now();
+    if suites.is_empty() {
+        println!("no test files found in {path}");
+        return Ok(());
+    }
     println!(
         "{} Tests loaded in {:.2} seconds",
-        suites.len() * suites[0].len(),
+        suites.iter().map(Vec::len).sum::<usize>(),
         end_load.duration_since(start_load).as_secs_f64()
     );
     let suites = suites.into_iter();
 
     let mut total_tests: f64 = 0.;
-    let mut total_successful: f64 = 0.;
+    let mut totals = [Tally::default(); MODES.len()];
 
     println!();
 
     let (sender, receiver) = std::sync::mpsc::channel();
 
     suites.for_each(|mut suite| {
-        let result = run_suite(&mut suite);
+        let result = run_suite(path, &mut suite);
         let _ = sender.clone().send(result);
     });
 
@@ -79,27 +107,41 @@ fn main() -> Result<()> {
 
     drop(sender);
 
-    for (successful, cases) in receiver {
+    for (cases, tallies) in receiver {
         total_tests += cases;
-        total_successful += successful;
+        for (total, tally) in totals.iter_mut().zip(tallies) {
+            total.successful += tally.successful;
+            total.bus_successful += tally.bus_successful;
+        }
     }
 
-    let total_failed: f64 = total_tests - total_successful;
-    let total_sucessful_percent: f64 = (total_successful / total_tests) * 100.;
-    let total_failed_percent: f64 = (total_failed / total_tests) * 100.;
+    for (mode, total) in MODES.iter().zip(totals) {
+        let total_failed: f64 = total_tests - total.successful;
+        let total_sucessful_percent: f64 = (total.successful / total_tests) * 100.;
+        let total_failed_percent: f64 = (total_failed / total_tests) * 100.;
+        let total_bus_percent: f64 = (total.bus_successful / total_tests) * 100.;
+
+        println!();
+        println!("{mode:?} stepped:");
+
+        println!(
+            "{:7}/{} ({:6.2}%) passed;",
+            total.successful, total_tests, total_sucessful_percent
+        );
+
+        println!(
+            "{:7}/{} ({:6.2}%) failed;",
+            total_failed, total_tests, total_failed_percent
+        );
+
+        println!(
+            "{:7}/{} ({:6.2}%) matched the expected bus cycles;",
+            total.bus_successful, total_tests, total_bus_percent
+        );
+    }
 
     println!();
 
-    println!(
-        "{:7}/{} ({:6.2}%) passed;",
-        total_successful, total_tests, total_sucessful_percent
-    );
-
-    println!(
-        "{:7}/{} ({:6.2}%) failed;",
-        total_failed, total_tests, total_failed_percent
-    );
-
     println!(
         "Ran all tests in {:.2}s;",
         total.duration_since(start).as_secs_f64(),
@@ -107,8 +149,30 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_suite(suite: &mut Vec<TestCase<IC6502>>) -> (f64, f64) {
-    let mut successful: f64 = 0.;
+/// How the CPU is driven through a test case
+#[derive(Debug, Copy, Clone)]
+enum Mode {
+    /// A whole instruction per call to [`OpenBusDevice::cycle`]
+    Instruction,
+    /// A single cycle per call to [`IC6502::tick`]
+    Cycle,
+}
+
+const MODES: [Mode; 2] = [Mode::Instruction, Mode::Cycle];
+
+#[derive(Debug, Copy, Clone, Default)]
+struct Tally {
+    successful: f64,
+    bus_successful: f64,
+}
+
+fn run_suite<V: Variant>(
+    path: &str,
+    suite: &mut Vec<TestCase<IC6502<V>>>,
+) -> (f64, [Tally; MODES.len()]) {
+    let mut tallies = [Tally::default(); MODES.len()];
+    let mut first_bus_mismatch = [const { None }; MODES.len()];
+    let mut first_error = [None; MODES.len()];
     let name = suite[0].name[0..2].to_owned();
     let cases = suite.len() as f64;
 
@@ -118,52 +182,116 @@ fn run_suite(suite: &mut Vec<TestCase<IC6502>>) -> (f64, f64) {
         // if case.name != "88 45 70" {
         //     continue;
         // }
-        let (pass, time) = run_test(case);
-        if pass {
-            successful += 1.;
+        case.target.ram.sort_by_key(|(addr, _)| *addr);
+
+        for (index, mode) in MODES.into_iter().enumerate() {
+            let result = run_test(case, mode);
+            if result.pass {
+                tallies[index].successful += 1.;
+            }
+            if let Some(error) = result.error {
+                first_error[index].get_or_insert(error);
+            }
+            match result.bus_mismatch {
+                None => tallies[index].bus_successful += 1.,
+                Some(mismatch) => {
+                    first_bus_mismatch[index].get_or_insert(mismatch);
+                }
+            }
+            start_instruction += result.time;
         }
-        start_instruction += time;
     }
     let end = std::time::Instant::now();
 
+    let [instruction, cycle] = tallies;
+
     println!(
-        "./65x02/nes6502/v1/{}.json: {:5}/{}; {:6.2}%;{:3}ms/{:3}µs;",
+        "{}/{}.json: {:5}/{} (bus {:5}); cycle stepped {:5}/{} (bus {:5}); {:6.2}%;{:3}ms/{:3}µs;",
+        path,
         name,
-        successful,
+        instruction.successful,
+        cases,
+        instruction.bus_successful,
+        cycle.successful,
         cases,
-        (successful as f64 / cases as f64) * 100.,
+        cycle.bus_successful,
+        (instruction.successful.min(cycle.successful) / cases) * 100.,
         end.duration_since(start).as_millis(),
         start_instruction
     );
 
-    (successful, cases)
+    for (mode, mismatch) in MODES.iter().zip(first_bus_mismatch) {
+        if let Some(mismatch) = mismatch {
+            println!("    {mode:?}: {mismatch}");
+        }
+    }
+
+    for (mode, error) in MODES.iter().zip(first_error) {
+        if let Some(error) = error {
+            println!("    {mode:?}: {error}");
+        }
+    }
+
+    (cases, tallies)
 }
 
-fn run_test(case: &mut TestCase<IC6502>) -> (bool, u128) {
-    let name = case.name.clone();
-    let cpu = &mut case.initial.cpu;
-    let ram = &mut case.initial.ram;
+struct TestResult {
+    pass: bool,
+    error: Option<CpuError>,
+    /// Description of the first cycle where the bus activity differed from the expected one
+    bus_mismatch: Option<String>,
+    time: u128,
+}
+
+fn run_test<V: Variant>(case: &TestCase<IC6502<V>>, mode: Mode) -> TestResult {
+    let mut cpu = case.initial.cpu.clone();
+    cpu.set_unstable_opcodes(UnstableOpcodes::SINGLE_STEP_TESTS);
+    let mut ram = case.initial.ram.clone();
+
+    let mut bus = TracingBus::new(&mut ram);
 
     let start = std::time::Instant::now();
-    cpu.cycle(ram).or_else(|| {
-        // drop(name);
-        // println!("CPU CRASH AT {}", name);
-        Some(8)
-    });
+    let cycles = match mode {
+        Mode::Instruction => cpu.cycle(&mut bus),
+        Mode::Cycle => run_cycles(&mut cpu, &mut bus),
+    };
     let end = std::time::Instant::now();
 
-    ram.sort_by(|(addr1, _), (addr2, _)| addr1.cmp(addr2));
+    let log = bus.into_log();
+
+    ram.sort_by_key(|(addr, _)| *addr);
 
-    case.target
-        .ram
-        .sort_by(|(addr1, _), (addr2, _)| addr1.cmp(addr2));
+    let ram_pass = ram == case.target.ram;
+    let cpu_pass = cpu.registers() == case.target.cpu.registers();
+    let cycles_pass = cycles == Ok(case.cycles.len() as u8);
 
-    let ram_pass = *ram == case.target.ram;
-    let cpu_pass = *cpu == case.target.cpu;
+    let bus_mismatch = case.cycle_mismatch(&log).map(|index| {
+        format!(
+            "{}: cycle {} expected {:?}, got {:?}",
+            case.name,
+            index,
+            case.cycles.get(index),
+            log.get(index)
+                .map(|access| (access.addr, access.value, access.kind))
+        )
+    });
 
-    if !ram_pass || !cpu_pass {
-        drop("".to_string())
+    TestResult {
+        pass: ram_pass && cpu_pass && cycles_pass,
+        error: cycles.err(),
+        bus_mismatch,
+        time: end.duration_since(start).as_micros(),
     }
+}
 
-    (ram_pass && cpu_pass, end.duration_since(start).as_micros())
+/// Ticks the CPU until it finished an instruction, returns the number of cycles it took
+fn run_cycles(
+    cpu: &mut IC6502<impl Variant>,
+    bus: &mut impl OpenBus,
+) -> std::result::Result<u8, CpuError> {
+    let mut cycles = 1;
+    while !cpu.tick(bus)? {
+        cycles += 1;
+    }
+    Ok(cycles)
 }
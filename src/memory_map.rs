@@ -0,0 +1,300 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    ops::RangeInclusive,
+};
+
+use crate::bus::{ADDRESS_SPACE, Bus, BusError, OpenBus};
+
+/// Something that can be put on a [`MemoryMap`].
+///
+/// It sees addresses relative to the start of its range, after mirroring
+pub trait MappedDevice: Bus + Any {}
+
+impl<D: Bus + Any> MappedDevice for D {}
+
+/// What accesses to addresses without a device do
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Unmapped {
+    /// Accesses fail with a [`BusError`]
+    #[default]
+    Fail,
+    /// Reads see the byte that was last on the data bus and writes go nowhere,
+    /// like on most consoles
+    OpenBus,
+}
+
+/// Handle of a device mapped onto a [`MemoryMap`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RegionId(usize);
+
+/// Entry of the decoder table for addresses without a device, also limits the number of regions
+const UNMAPPED: u8 = u8::MAX;
+
+struct Region {
+    range: RangeInclusive<u16>,
+    mirror_mask: u16,
+    device: Box<dyn MappedDevice>,
+}
+
+/// Address decoder that puts devices onto ranges of the address space.
+///
+/// Where ranges overlap the device mapped last wins,
+/// so e.g. a cartridge can be put over memory that was mapped before
+pub struct MemoryMap {
+    regions: Vec<Region>,
+    /// Index of the region every address decodes to, filled in when devices are mapped
+    decoder: Box<[u8]>,
+    /// Lowest address that reaches the same byte of the same device, for every address
+    mirrors: Box<[u16]>,
+    unmapped: Unmapped,
+    /// Last byte that was transferred, reads from unmapped addresses can see it
+    data_bus: Cell<u8>,
+}
+
+impl MemoryMap {
+    pub fn new(unmapped: Unmapped) -> Self {
+        Self {
+            regions: Vec::new(),
+            decoder: vec![UNMAPPED; ADDRESS_SPACE].into_boxed_slice(),
+            mirrors: (0..=u16::MAX).collect(),
+            unmapped,
+            data_bus: Cell::new(0),
+        }
+    }
+
+    /// Maps a device onto a range, it sees the offset into the range as its address.
+    /// Panics if the device is smaller than the range
+    pub fn map(&mut self, range: RangeInclusive<u16>, device: impl MappedDevice) -> RegionId {
+        self.map_mirrored(range, u16::MAX, device)
+    }
+
+    /// Maps a device that repeats over the range,
+    /// it sees the offset into the range ANDed with `mirror_mask` as its address.
+    ///
+    /// 2 KiB of RAM mirrored across `$0000-$1FFF` is mapped with a mask of `$07FF`,
+    /// eight registers mirrored up to `$3FFF` with a mask of `$0007`.
+    /// Panics if the device is too small for every address it would see
+    pub fn map_mirrored(
+        &mut self,
+        range: RangeInclusive<u16>,
+        mirror_mask: u16,
+        device: impl MappedDevice,
+    ) -> RegionId {
+        assert!(!range.is_empty(), "a device needs at least one address");
+        assert!(self.regions.len() < UNMAPPED as usize, "too many devices");
+        if let Some(size) = device.size() {
+            let highest = (0..=range.end() - range.start())
+                .map(|offset| offset & mirror_mask)
+                .max()
+                .unwrap_or_default();
+            assert!(
+                (highest as usize) < size,
+                "a device of {size} bytes can't be mapped to {range:04X?} with a mirror mask of ${mirror_mask:04X}"
+            );
+        }
+
+        let index = self.regions.len();
+        self.decoder[*range.start() as usize..=*range.end() as usize].fill(index as u8);
+        self.regions.push(Region {
+            range,
+            mirror_mask,
+            device: Box::new(device),
+        });
+        self.find_mirrors();
+        RegionId(index)
+    }
+
+    /// The mapped device, `None` if it isn't a `D` or was mapped onto another map
+    pub fn device<D: MappedDevice>(&self, id: RegionId) -> Option<&D> {
+        let device: &dyn Any = self.regions.get(id.0)?.device.as_ref();
+        device.downcast_ref()
+    }
+
+    /// The mapped device, `None` if it isn't a `D` or was mapped onto another map
+    pub fn device_mut<D: MappedDevice>(&mut self, id: RegionId) -> Option<&mut D> {
+        let device: &mut dyn Any = self.regions.get_mut(id.0)?.device.as_mut();
+        device.downcast_mut()
+    }
+
+    /// Byte that was last on the data bus
+    pub fn data_bus(&self) -> u8 {
+        self.data_bus.get()
+    }
+
+    /// Fills in the lowest address every address mirrors
+    fn find_mirrors(&mut self) {
+        let mut lowest = HashMap::new();
+        for addr in 0..=u16::MAX {
+            self.mirrors[addr as usize] = match self.decode(addr) {
+                Some(target) => *lowest.entry(target).or_insert(addr),
+                None => addr,
+            };
+        }
+    }
+
+    /// Finds the region an address belongs to and the address its device sees
+    fn decode(&self, addr: u16) -> Option<(usize, u16)> {
+        let index = self.decoder[addr as usize];
+        if index == UNMAPPED {
+            return None;
+        }
+
+        let region = &self.regions[index as usize];
+        Some((
+            index as usize,
+            (addr - region.range.start()) & region.mirror_mask,
+        ))
+    }
+}
+
+impl Default for MemoryMap {
+    fn default() -> Self {
+        Self::new(Unmapped::default())
+    }
+}
+
+impl Debug for MemoryMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let ranges: Vec<_> = self.regions.iter().map(|region| &region.range).collect();
+        f.debug_struct("MemoryMap")
+            .field("regions", &ranges)
+            .field("unmapped", &self.unmapped)
+            .field("data_bus", &self.data_bus.get())
+            .finish()
+    }
+}
+
+impl OpenBus for MemoryMap {
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
+        let value = match (self.decode(addr), self.unmapped) {
+            (Some((index, local)), _) => self.regions[index].device.read(local),
+            (None, Unmapped::OpenBus) => self.data_bus.get(),
+            (None, Unmapped::Fail) => return Err(BusError::read(addr)),
+        };
+        self.data_bus.set(value);
+        Ok(value)
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
+        match (self.decode(addr), self.unmapped) {
+            (Some((index, local)), _) => self.regions[index].device.write(local, byte),
+            (None, Unmapped::OpenBus) => {}
+            (None, Unmapped::Fail) => return Err(BusError::write(addr)),
+        }
+        self.data_bus.set(byte);
+        Ok(())
+    }
+    fn canonical_address(&self, addr: u16) -> u16 {
+        self.mirrors[addr as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Device that answers reads with the address it saw and remembers the last write
+    #[derive(Debug, Default)]
+    struct Echo {
+        written: Option<(u16, u8)>,
+    }
+
+    impl Bus for Echo {
+        fn read(&self, addr: u16) -> u8 {
+            addr as u8
+        }
+
+        fn write(&mut self, addr: u16, byte: u8) {
+            self.written = Some((addr, byte));
+        }
+    }
+
+    #[test]
+    fn mirroring() {
+        let mut map = MemoryMap::default();
+        map.map_mirrored(0x0000..=0x1FFF, 0x07FF, [0u8; 0x800]);
+        map.map_mirrored(0x2000..=0x3FFF, 0x0007, Echo::default());
+
+        map.write(0x0801, 0x42).unwrap();
+        for addr in [0x0001, 0x0801, 0x1001, 0x1801] {
+            assert_eq!(map.read(addr), Ok(0x42));
+        }
+        assert_eq!(map.read(0x2000), Ok(0x00));
+        assert_eq!(map.read(0x2009), Ok(0x01));
+        assert_eq!(map.read(0x3FFF), Ok(0x07));
+
+        assert_eq!(map.canonical_address(0x1801), 0x0001);
+        assert_eq!(map.canonical_address(0x3FFF), 0x2007);
+        assert_eq!(map.canonical_address(0x4000), 0x4000);
+    }
+
+    #[test]
+    fn later_devices_win() {
+        let mut map = MemoryMap::default();
+        let below = map.map(0x0000..=0xFFFF, Echo::default());
+        let above = map.map(0x8000..=0x80FF, [0xEAu8; 0x100]);
+
+        assert_eq!(map.read(0x7FFF), Ok(0xFF));
+        assert_eq!(map.read(0x8010), Ok(0xEA));
+        assert_eq!(map.read(0x8110), Ok(0x10));
+        assert_eq!(map.canonical_address(0x8010), 0x8010);
+
+        map.write(0x8010, 0x00).unwrap();
+        map.write(0x8100, 0x01).unwrap();
+        assert_eq!(
+            map.device::<Echo>(below).unwrap().written,
+            Some((0x8100, 0x01))
+        );
+        assert_eq!(map.device::<[u8; 0x100]>(above).unwrap()[0x10], 0x00);
+    }
+
+    #[test]
+    fn unmapped_fail() {
+        let mut map = MemoryMap::new(Unmapped::Fail);
+        map.map(0x0000..=0x00FF, [0u8; 0x100]);
+
+        assert_eq!(map.read(0x0100), Err(BusError::read(0x0100)));
+        assert_eq!(map.write(0xFFFF, 0x00), Err(BusError::write(0xFFFF)));
+    }
+
+    #[test]
+    fn unmapped_open_bus() {
+        let mut map = MemoryMap::new(Unmapped::OpenBus);
+        map.map(0x0000..=0x00FF, [0x12u8; 0x100]);
+
+        assert_eq!(map.read(0x0010), Ok(0x12));
+        assert_eq!(map.read(0x4000), Ok(0x12));
+        map.write(0x4000, 0x34).unwrap();
+        assert_eq!(map.data_bus(), 0x34);
+        assert_eq!(map.read(0x5000), Ok(0x34));
+        assert_eq!(map.read(0x0000), Ok(0x12));
+    }
+
+    #[test]
+    fn device_lookup() {
+        let mut map = MemoryMap::default();
+        let ram = map.map(0x0000..=0x07FF, [0u8; 0x800]);
+        let echo = map.map(0x2000..=0x2007, Echo::default());
+
+        map.device_mut::<[u8; 0x800]>(ram).unwrap()[0x0123] = 0x45;
+        assert_eq!(map.read(0x0123), Ok(0x45));
+        assert!(map.device::<Echo>(echo).is_some());
+        assert!(map.device::<Echo>(ram).is_none());
+        assert!(MemoryMap::default().device::<Echo>(echo).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be mapped")]
+    fn ram_smaller_than_the_range() {
+        MemoryMap::default().map(0x0000..=0x1FFF, [0u8; 0x800]);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be mapped")]
+    fn ram_smaller_than_the_mirror_mask() {
+        MemoryMap::default().map_mirrored(0x0000..=0x1FFF, 0x0FFF, [0u8; 0x800]);
+    }
+}
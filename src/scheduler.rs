@@ -0,0 +1,537 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    convert::Infallible,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+use crate::{
+    bus::{OpenBus, OpenBusDevice},
+    ic6502::{CpuError, Hook, IC6502, Ricoh2A03, Variant},
+};
+
+/// How a console divides its master clock down for the CPU and the PPU
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Timing {
+    /// Frequency of the master clock in Hz
+    pub master_clock: u64,
+    pub cpu_divider: u32,
+    pub ppu_divider: u32,
+}
+
+impl Timing {
+    /// 3 PPU dots per CPU cycle
+    pub const NTSC: Timing = Timing {
+        master_clock: 21_477_272,
+        cpu_divider: 12,
+        ppu_divider: 4,
+    };
+
+    /// 3.2 PPU dots per CPU cycle
+    pub const PAL: Timing = Timing {
+        master_clock: 26_601_712,
+        cpu_divider: 16,
+        ppu_divider: 5,
+    };
+}
+
+/// Code that gets to change the CPU and the bus when its [`Event`] is due
+pub type Callback<B, V> = Box<dyn FnOnce(&mut IC6502<V>, &mut B)>;
+
+/// Something that happens at an exact time of the master clock
+pub enum Event<B, V: Variant = Ricoh2A03> {
+    Irq(bool),
+    Nmi(bool),
+    Reset,
+    /// Level of the SO pin
+    So(bool),
+    /// Level of the RDY line, `false` stalls the CPU like the DMA of the NES does
+    Rdy(bool),
+    /// Anything else, like a device changing its state
+    Custom(Callback<B, V>),
+}
+
+impl<B, V: Variant> Debug for Event<B, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Irq(asserted) => f.debug_tuple("Irq").field(asserted).finish(),
+            Event::Nmi(asserted) => f.debug_tuple("Nmi").field(asserted).finish(),
+            Event::Reset => f.write_str("Reset"),
+            Event::So(asserted) => f.debug_tuple("So").field(asserted).finish(),
+            Event::Rdy(ready) => f.debug_tuple("Rdy").field(ready).finish(),
+            Event::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// An event waiting for its time, ordered so the earliest one comes out of the heap first
+struct Pending<B, V: Variant> {
+    at: u64,
+    /// Keeps events at the same time in the order they were scheduled
+    order: u64,
+    event: Event<B, V>,
+}
+
+impl<B, V: Variant> PartialEq for Pending<B, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<B, V: Variant> Eq for Pending<B, V> {}
+
+impl<B, V: Variant> PartialOrd for Pending<B, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<B, V: Variant> Ord for Pending<B, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.at, other.order).cmp(&(self.at, self.order))
+    }
+}
+
+/// Events in the order they take effect
+struct Events<B, V: Variant> {
+    heap: BinaryHeap<Pending<B, V>>,
+    scheduled: u64,
+}
+
+impl<B, V: Variant> Events<B, V> {
+    fn push(&mut self, at: u64, event: Event<B, V>) {
+        self.heap.push(Pending {
+            at,
+            order: self.scheduled,
+            event,
+        });
+        self.scheduled += 1;
+    }
+}
+
+/// What a [`Device`] gets to work with while it runs
+pub struct Context<'a, B, V: Variant = Ricoh2A03> {
+    cpu: &'a mut IC6502<V>,
+    bus: &'a mut B,
+    events: &'a mut Events<B, V>,
+    clock: u64,
+}
+
+impl<B, V: Variant> Context<'_, B, V> {
+    /// Current cycle of the master clock
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    pub fn cpu(&self) -> &IC6502<V> {
+        self.cpu
+    }
+
+    /// Lines set through the CPU, like IRQ, NMI or RDY, are seen by its next cycle
+    pub fn cpu_mut(&mut self) -> &mut IC6502<V> {
+        self.cpu
+    }
+
+    pub fn bus(&self) -> &B {
+        self.bus
+    }
+
+    pub fn bus_mut(&mut self) -> &mut B {
+        self.bus
+    }
+
+    /// Queues an event like [`Scheduler::schedule`]
+    pub fn schedule(&mut self, at: u64, event: Event<B, V>) {
+        self.events.push(at, event);
+    }
+
+    /// Queues an event `delay` cycles of the master clock from now
+    pub fn schedule_in(&mut self, delay: u64, event: Event<B, V>) {
+        self.events.push(self.clock + delay, event);
+    }
+}
+
+/// Something a [`Scheduler`] drives next to the CPU.
+///
+/// Every [`OpenBusDevice`] is one that only gets to see the bus
+pub trait Device<B, V: Variant = Ricoh2A03> {
+    type Error;
+
+    /// Runs the device, returns how many cycles of its own clock it ran for
+    fn cycle(&mut self, context: &mut Context<'_, B, V>) -> Result<u8, Self::Error>;
+}
+
+impl<B: OpenBus, V: Variant, D: OpenBusDevice<B>> Device<B, V> for D {
+    type Error = D::Error;
+
+    fn cycle(&mut self, context: &mut Context<'_, B, V>) -> Result<u8, Self::Error> {
+        OpenBusDevice::cycle(self, context.bus_mut())
+    }
+}
+
+/// Handle of a device added to a [`Scheduler`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DeviceId(usize);
+
+struct Slot<B, V: Variant, E> {
+    device: Box<dyn Device<B, V, Error = E>>,
+    divider: u32,
+    /// Master clock cycle the device runs next
+    next: u64,
+}
+
+/// Failure of the CPU or one of the devices driven by a [`Scheduler`]
+#[derive(Debug)]
+pub enum SchedulerError<E> {
+    Cpu(CpuError),
+    Device(DeviceId, E),
+}
+
+impl<E: Display> Display for SchedulerError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SchedulerError::Cpu(error) => write!(f, "{error}"),
+            SchedulerError::Device(DeviceId(index), error) => {
+                write!(f, "device {index} failed: {error}")
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for SchedulerError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SchedulerError::Cpu(error) => Some(error),
+            SchedulerError::Device(_, error) => Some(error),
+        }
+    }
+}
+
+/// Drives the CPU and the devices next to it off a shared master clock.
+///
+/// Every device runs at the master clock divided by its own divider,
+/// whoever is furthest behind runs next and the CPU goes first on a tie.
+/// The CPU is ticked a single cycle at a time, so it sees every event on the exact cycle.
+///
+/// Devices get a [`Context`] handed in when they run, through it they reach the bus,
+/// drive the lines of the CPU and schedule events
+pub struct Scheduler<B: OpenBus, V: Variant = Ricoh2A03, E = Infallible> {
+    cpu: IC6502<V>,
+    bus: B,
+    cpu_divider: u32,
+    /// Master clock cycle the CPU runs next
+    cpu_next: u64,
+    devices: Vec<Slot<B, V, E>>,
+    events: Events<B, V>,
+    clock: u64,
+}
+
+impl<B: OpenBus, V: Variant, E> Scheduler<B, V, E> {
+    pub fn new(cpu: IC6502<V>, bus: B, cpu_divider: u32) -> Self {
+        assert!(cpu_divider > 0, "the CPU needs a clock");
+        Self {
+            cpu,
+            bus,
+            cpu_divider,
+            cpu_next: 0,
+            devices: Vec::new(),
+            events: Events {
+                heap: BinaryHeap::new(),
+                scheduled: 0,
+            },
+            clock: 0,
+        }
+    }
+
+    /// Adds a device that runs once every `divider` cycles of the master clock,
+    /// starting with the current one
+    pub fn add_device(
+        &mut self,
+        device: impl Device<B, V, Error = E> + 'static,
+        divider: u32,
+    ) -> DeviceId {
+        assert!(divider > 0, "a device needs a clock");
+        self.devices.push(Slot {
+            device: Box::new(device),
+            divider,
+            next: self.clock,
+        });
+        DeviceId(self.devices.len() - 1)
+    }
+
+    /// Current cycle of the master clock
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    /// Cycles the CPU ran for so far
+    pub fn cpu_cycles(&self) -> u64 {
+        self.cpu_next / self.cpu_divider as u64
+    }
+
+    pub fn cpu(&self) -> &IC6502<V> {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut IC6502<V> {
+        &mut self.cpu
+    }
+
+    pub fn bus(&self) -> &B {
+        &self.bus
+    }
+
+    pub fn bus_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+
+    /// Queues an event for the given cycle of the master clock.
+    ///
+    /// It takes effect right before whatever runs first on or after that cycle,
+    /// events on the same cycle take effect in the order they were scheduled
+    pub fn schedule(&mut self, at: u64, event: Event<B, V>) {
+        self.events.push(at, event);
+    }
+
+    /// Queues an event `delay` cycles of the master clock from now
+    pub fn schedule_in(&mut self, delay: u64, event: Event<B, V>) {
+        self.schedule(self.clock + delay, event);
+    }
+
+    /// Runs everything up to, but not including, the given cycle of the master clock
+    pub fn run_until(&mut self, end: u64) -> Result<(), SchedulerError<E>> {
+        self.run_until_with_hooks(end, &mut ())
+    }
+
+    /// Runs like [`Scheduler::run_until`] and shows the instructions of the CPU to the hooks
+    pub fn run_until_with_hooks(
+        &mut self,
+        end: u64,
+        hooks: &mut impl Hook<V>,
+    ) -> Result<(), SchedulerError<E>> {
+        while self.step_before(end, hooks)? {}
+        self.clock = self.clock.max(end);
+        Ok(())
+    }
+
+    /// Runs everything for the given number of master clock cycles
+    pub fn run_for(&mut self, cycles: u64) -> Result<(), SchedulerError<E>> {
+        self.run_until(self.clock + cycles)
+    }
+
+    /// Runs whatever is furthest behind, returns false if nothing is due before `end`
+    fn step_before(
+        &mut self,
+        end: u64,
+        hooks: &mut impl Hook<V>,
+    ) -> Result<bool, SchedulerError<E>> {
+        let device = self
+            .devices
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, slot)| slot.next)
+            .filter(|(_, slot)| slot.next < self.cpu_next)
+            .map(|(index, _)| index);
+
+        let next = device.map_or(self.cpu_next, |index| self.devices[index].next);
+        if next >= end {
+            return Ok(false);
+        }
+
+        self.clock = next;
+        self.deliver_events();
+
+        match device {
+            None => {
+                self.cpu
+                    .tick_with_hooks(&mut self.bus, hooks)
+                    .map_err(SchedulerError::Cpu)?;
+                self.cpu_next += self.cpu_divider as u64;
+            }
+            Some(index) => {
+                let slot = &mut self.devices[index];
+                let mut context = Context {
+                    cpu: &mut self.cpu,
+                    bus: &mut self.bus,
+                    events: &mut self.events,
+                    clock: self.clock,
+                };
+                let cycles = slot
+                    .device
+                    .cycle(&mut context)
+                    .map_err(|error| SchedulerError::Device(DeviceId(index), error))?;
+                slot.next += cycles.max(1) as u64 * slot.divider as u64;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Lets every event that is due take effect
+    fn deliver_events(&mut self) {
+        while self
+            .events
+            .heap
+            .peek()
+            .is_some_and(|pending| pending.at <= self.clock)
+        {
+            let Some(pending) = self.events.heap.pop() else {
+                break;
+            };
+
+            match pending.event {
+                Event::Irq(asserted) => self.cpu.set_irq(asserted),
+                Event::Nmi(asserted) => self.cpu.set_nmi(asserted),
+                Event::Reset => self.cpu.reset(),
+                Event::So(asserted) => self.cpu.set_so(asserted),
+                Event::Rdy(ready) => self.cpu.set_ready(ready),
+                Event::Custom(event) => event(&mut self.cpu, &mut self.bus),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::bus::FlatMemory;
+
+    /// CLI and NOPs from `$0200` on, the NMI and IRQ handlers start at `$0300` and `$0400`
+    fn scheduler(cpu_divider: u32) -> Scheduler<FlatMemory> {
+        let mut memory = FlatMemory::new();
+        memory.as_mut_slice().fill(0xEA);
+        memory.load(0x0200, &[0x58]);
+        memory.set_nmi_vector(0x0300);
+        memory.set_irq_vector(0x0400);
+        let cpu = IC6502::builder().program_counter(0x0200).build();
+        Scheduler::new(cpu, memory, cpu_divider)
+    }
+
+    /// Counts how often it ran, every run takes `cycles` of its own clock
+    struct Counter {
+        runs: Rc<Cell<u64>>,
+        cycles: u8,
+    }
+
+    impl<B: OpenBus> OpenBusDevice<B> for Counter {
+        type Error = Infallible;
+
+        fn cycle(&mut self, _bus: &mut B) -> Result<u8, Infallible> {
+            self.runs.set(self.runs.get() + 1);
+            Ok(self.cycles)
+        }
+    }
+
+    fn counter(cycles: u8) -> (Counter, Rc<Cell<u64>>) {
+        let runs = Rc::new(Cell::new(0));
+        let counter = Counter {
+            runs: runs.clone(),
+            cycles,
+        };
+        (counter, runs)
+    }
+
+    #[test]
+    fn dividers() {
+        let mut scheduler = scheduler(12);
+        let (ppu, dots) = counter(1);
+        let (slow, slow_runs) = counter(3);
+        scheduler.add_device(ppu, 4);
+        scheduler.add_device(slow, 5);
+
+        scheduler.run_for(1200).unwrap();
+        assert_eq!(scheduler.cpu_cycles(), 100);
+        assert_eq!(dots.get(), 300);
+        // every run of it takes 15 cycles of the master clock
+        assert_eq!(slow_runs.get(), 80);
+        assert_eq!(scheduler.clock(), 1200);
+    }
+
+    /// Pulls NMI low on one cycle of the master clock
+    struct NmiSource {
+        at: u64,
+    }
+
+    impl Device<FlatMemory> for NmiSource {
+        type Error = Infallible;
+
+        fn cycle(&mut self, context: &mut Context<'_, FlatMemory>) -> Result<u8, Infallible> {
+            if context.clock() == self.at {
+                context.cpu_mut().set_nmi(true);
+            }
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn device_raises_nmi() {
+        // the CPU runs first on cycle 10, the fetch of the instruction at $0205.
+        // Its last cycle looks at what the fetch polled, so the one at $0206 runs as well
+        // and the sequence takes cycles 14 to 20
+        let mut scheduler = scheduler(1);
+        scheduler.add_device(NmiSource { at: 10 }, 1);
+
+        scheduler.run_until(20).unwrap();
+        assert_eq!(scheduler.cpu().program_counter(), 0x0207);
+        scheduler.run_until(21).unwrap();
+        assert_eq!(scheduler.cpu().program_counter(), 0x0300);
+        let stack = scheduler.cpu().stack_pointer() as usize + 0x0100;
+        assert_eq!(
+            scheduler.bus().as_slice()[stack + 2..=stack + 3],
+            [0x07, 0x02]
+        );
+    }
+
+    /// Raises IRQ through an event a few cycles after it first ran
+    struct IrqSource {
+        delay: u64,
+        scheduled: bool,
+    }
+
+    impl Device<FlatMemory> for IrqSource {
+        type Error = Infallible;
+
+        fn cycle(&mut self, context: &mut Context<'_, FlatMemory>) -> Result<u8, Infallible> {
+            if !self.scheduled {
+                context.schedule_in(self.delay, Event::Irq(true));
+                self.scheduled = true;
+            }
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn device_schedules_events() {
+        let mut scheduler = scheduler(1);
+        scheduler.add_device(
+            IrqSource {
+                delay: 11,
+                scheduled: false,
+            },
+            1,
+        );
+
+        // the event takes effect before the CPU runs cycle 11, like an NMI raised on cycle 10
+        scheduler.run_until(20).unwrap();
+        assert_eq!(scheduler.cpu().program_counter(), 0x0207);
+        scheduler.run_until(21).unwrap();
+        assert_eq!(scheduler.cpu().program_counter(), 0x0400);
+    }
+
+    #[test]
+    fn events_on_the_same_cycle_in_order() {
+        let run = |first, second| {
+            let mut scheduler = scheduler(1);
+            scheduler.schedule(2, Event::Irq(first));
+            scheduler.schedule(2, Event::Irq(second));
+            scheduler.run_until(30).unwrap();
+            scheduler.cpu().program_counter()
+        };
+
+        assert_eq!(run(true, false), 0x020F);
+        assert_eq!(run(false, true) & 0xFF00, 0x0400);
+    }
+}
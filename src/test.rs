@@ -1,12 +1,33 @@
 use serde_derive::{Deserialize, Serialize};
 
+use crate::bus::{AccessKind, BusAccess, BusError};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TestCase<T> {
     pub name: String,
     pub initial: State<T>,
     #[serde(rename = "final")]
     pub target: State<T>,
-    pub cycles: Vec<(u16, u8, String)>,
+    pub cycles: Vec<(u16, u8, AccessKind)>,
+}
+
+impl<T> TestCase<T> {
+    /// Compares a recorded bus log against the expected cycles
+    ///
+    /// Returns the index of the first cycle that doesn't match
+    pub fn cycle_mismatch(&self, log: &[BusAccess]) -> Option<usize> {
+        let mismatch = self
+            .cycles
+            .iter()
+            .zip(log)
+            .position(|(&(addr, value, kind), access)| BusAccess { addr, value, kind } != *access);
+
+        match mismatch {
+            Some(index) => Some(index),
+            None if self.cycles.len() != log.len() => Some(self.cycles.len().min(log.len())),
+            None => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,23 +38,24 @@ pub struct State<T> {
 }
 
 impl crate::bus::OpenBus for Vec<(u16, u8)> {
-    fn read(&self, addr: u16) -> Option<u8> {
+    fn read(&self, addr: u16) -> Result<u8, BusError> {
         self.iter()
             .find_map(|(ram_addr, val)| match *ram_addr == addr {
                 true => Some(*val),
                 false => None,
             })
+            .ok_or(BusError::read(addr))
     }
 
-    fn write(&mut self, addr: u16, byte: u8) -> Option<()> {
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), BusError> {
         for (ram_addr, val) in self.iter_mut() {
             if *ram_addr != addr {
                 continue;
             }
             *val = byte;
-            return Some(());
+            return Ok(());
         }
         self.push((addr, byte));
-        Some(())
+        Ok(())
     }
 }